@@ -3,18 +3,35 @@
 use eframe::egui;
 use rand::Rng;
 use rdev::{listen, Event, EventType, Key, simulate, Button};
-use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::sync::{Arc, Mutex, OnceLock, atomic::{AtomicBool, Ordering}};
 use std::thread;
-use std::time::{Duration, Instant};
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::collections::HashMap;
+use std::io::Write;
 use serde::{Deserialize, Serialize};
+use regex::Regex;
 use windows::Win32::{
-    Foundation::{HWND, LPARAM, BOOL, RECT, WPARAM},
+    Foundation::{CloseHandle, HWND, LPARAM, BOOL, POINT, RECT, WPARAM},
+    Graphics::{
+        Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED},
+        Gdi::{
+            DeleteObject, GetDC, GetDIBits, GetObjectW, MapWindowPoints, ReleaseDC, ScreenToClient, BITMAP, BITMAPINFO,
+            BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP,
+        },
+    },
+    UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_MBUTTON, VK_XBUTTON1, VK_XBUTTON2},
+    System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+    },
     UI::WindowsAndMessaging::{
-        EnumWindows, GetClientRect, GetWindowTextLengthW, GetWindowTextW, IsWindowVisible,
-        PostMessageW, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_RBUTTONDOWN, WM_RBUTTONUP,
-        WM_KEYDOWN, WM_KEYUP,
+        EnumChildWindows, EnumWindows, GetAncestor, GetClassNameW, GetCursorPos, GetClientRect, GetForegroundWindow, GetParent, GetWindowLongW, GetWindowRect, GetWindowTextLengthW, GetWindowTextW,
+        GetWindowThreadProcessId, IsWindow, IsWindowVisible, PostMessageW, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        GetClassLongPtrW, GetIconInfo, SendMessageTimeoutW, GCLP_HICON, GCLP_HICONSM, HICON, ICONINFO, ICON_BIG,
+        ICON_SMALL, ICON_SMALL2, SMTO_ABORTIFHUNG, WM_GETICON,
+        GetSystemMetrics, WindowFromPoint, GA_ROOT, GWL_EXSTYLE, SM_CXSCREEN, SM_CYSCREEN, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_KEYDOWN, WM_KEYUP, WS_EX_TOOLWINDOW,
     },
 };
 
@@ -23,18 +40,68 @@ struct PyladeClickerApp {
     clicking: Arc<AtomicBool>,
     click_mode: Arc<Mutex<ClickMode>>,
     click_type: Arc<Mutex<ClickType>>,
-    target_window: Arc<Mutex<Option<String>>>,
-    windows: Vec<String>,
+    targets: Arc<Mutex<Vec<ClickTarget>>>,
+    target_strategy: Arc<Mutex<TargetStrategy>>,
+    target_loss_action: Arc<Mutex<TargetLossAction>>,
+    target_state: Arc<Mutex<TargetState>>,
+    click_condition: Arc<Mutex<ClickCondition>>,
+    condition_waiting: Arc<AtomicBool>,
+    pause_on_input: Arc<AtomicBool>,
+    pause_idle: Arc<Mutex<Duration>>,
+    input_paused_until: Arc<Mutex<Option<Instant>>>,
+    input_paused: Arc<AtomicBool>,
+    windows: Vec<WindowInfo>,
+    window_filter: String,
+    window_icons: HashMap<isize, Option<egui::TextureHandle>>,
+    foreground_history: Vec<isize>,
     _last_click_time: Arc<Mutex<Instant>>,
     _humanized_delay: Arc<Mutex<Duration>>,
     normal_delay: Arc<Mutex<Duration>>,
     cps: Arc<Mutex<f32>>,
-    hotkey: Arc<Mutex<Vec<Key>>>,
+    hotkey: Arc<Mutex<Vec<HotkeyInput>>>,
     capturing_hotkey: Arc<AtomicBool>,
     listening_text: Arc<Mutex<String>>,
-    current_combination: Arc<Mutex<Vec<Key>>>,
+    current_combination: Arc<Mutex<Vec<HotkeyInput>>>,
+    captured_hotkey: Arc<Mutex<Option<Vec<HotkeyInput>>>>,
     last_window_refresh: Arc<Mutex<Instant>>,
     is_holding: Arc<AtomicBool>,
+    activation_style: Arc<Mutex<ActivationStyle>>,
+    one_shot_clicks: Arc<Mutex<u32>>,
+    one_shot_remaining: Arc<Mutex<Option<u32>>>,
+    start_hotkey: Arc<Mutex<Vec<HotkeyInput>>>,
+    stop_hotkey: Arc<Mutex<Vec<HotkeyInput>>>,
+    panic_hotkey: Arc<Mutex<Vec<HotkeyInput>>>,
+    pick_target_hotkey: Arc<Mutex<Vec<HotkeyInput>>>,
+    pick_point: Arc<AtomicBool>,
+    input_failures: Arc<Mutex<u32>>,
+    input_error: Arc<Mutex<Option<String>>>,
+    failsafe_zone: Arc<Mutex<FailsafeZone>>,
+    failsafe_rect: Arc<Mutex<[i32; 4]>>,
+    hotkeys_armed: Arc<AtomicBool>,
+    capture_slot: Arc<Mutex<HotkeySlot>>,
+    profiles: Arc<Mutex<Vec<ProfileConfig>>>,
+    active_profile: Arc<Mutex<String>>,
+    profile_switch_hotkey: Arc<Mutex<Vec<HotkeyInput>>>,
+    profile_name_edit: String,
+    profile_name_edit_for: String,
+    auto_rules: Arc<Mutex<Vec<AutoProfileRule>>>,
+    clicker_armed: Arc<AtomicBool>,
+    last_foreground: Option<(isize, String)>,
+    disarmed_by_rule: bool,
+    config_error: Arc<Mutex<Option<String>>>,
+    save_requested: Arc<Mutex<Option<Instant>>>,
+    config_save_error: Arc<Mutex<Option<String>>>,
+    export_selection: Vec<String>,
+    bundle_path: String,
+    import_text: String,
+    pending_import: Vec<PendingImport>,
+    bundle_status: Option<String>,
+    control_picker: Option<(usize, Vec<ChildInfo>)>,
+    config_mtime: Arc<Mutex<Option<SystemTime>>>,
+    external_config: Arc<Mutex<Option<AppConfig>>>,
+    external_config_error: Arc<Mutex<Option<String>>>,
+    session_log: Arc<Mutex<Vec<String>>>,
+    session_start: Instant,
 }
 
 #[derive(Clone, PartialEq)]
@@ -51,1110 +118,4007 @@ enum ClickType {
     Space,
 }
 
+#[derive(Clone, PartialEq)]
+enum TargetStrategy {
+    RoundRobin,
+    AllAtOnce,
+    Independent,
+}
+
+#[derive(Clone, PartialEq)]
+enum TargetLossAction {
+    Stop,
+    Pause,
+    Global,
+}
+
+#[derive(Clone, PartialEq)]
+enum ClickCondition {
+    Always,
+    Foreground,
+    Background,
+    CursorInside,
+}
+
+/// Whether the clicking thread can find its targets, and what it is doing
+/// about it if not.
+#[derive(Clone, Copy, PartialEq)]
+enum TargetState {
+    Found,
+    Paused,
+    GlobalFallback,
+}
+
+/// Where slamming the physical cursor stops everything.
+#[derive(Clone, Copy, PartialEq)]
+enum FailsafeZone {
+    Off,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Rect,
+}
+
+#[derive(Clone, PartialEq)]
+enum ActivationStyle {
+    Toggle,
+    HoldToRun,
+    OneShot,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum HotkeyInput {
+    Key(Key),
+    Button(Button),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum HotkeySlot {
+    Activation,
+    Start,
+    Stop,
+    Panic,
+    ProfileSwitch,
+    PickTarget,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
-struct AppConfig {
+#[serde(default)]
+struct ProfileConfig {
+    name: String,
     hotkey: Vec<String>,
+    switch_hotkey: Vec<String>,
     click_mode: String,
     click_type: String,
     normal_delay_ms: u64,
     cps: f32,
+    activation_style: String,
+    one_shot_clicks: u32,
+    targets: Vec<ClickTarget>,
+    target_strategy: String,
+    target_loss_action: String,
+    click_condition: String,
+    pause_on_input: bool,
+    pause_idle_ms: u64,
 }
 
-impl Default for AppConfig {
+impl Default for ProfileConfig {
     fn default() -> Self {
         Self {
+            name: "Default".to_string(),
             hotkey: vec!["F6".to_string()],
+            switch_hotkey: Vec::new(),
             click_mode: "Click".to_string(),
             click_type: "LeftClick".to_string(),
             normal_delay_ms: 1000,
             cps: 10.0,
+            activation_style: "Toggle".to_string(),
+            one_shot_clicks: 10,
+            targets: Vec::new(),
+            target_strategy: "RoundRobin".to_string(),
+            target_loss_action: "Pause".to_string(),
+            click_condition: "Always".to_string(),
+            pause_on_input: false,
+            pause_idle_ms: 1000,
+        }
+    }
+}
+
+/// Finds the target window by comparing `pattern` against the part of each
+/// top-level window named by `kind`, one of `WINDOW_MATCH_KINDS`. Executable
+/// names are compared case-insensitively, everything else exactly.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+struct WindowMatchRule {
+    kind: String,
+    pattern: String,
+}
+
+/// One window to click, at `point` in its client area or else at the centre.
+/// The schedule fields are only used by the `Independent` strategy; the
+/// other strategies follow the profile's mode and delay.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+struct ClickTarget {
+    rule: WindowMatchRule,
+    child: Option<ChildControl>,
+    point: Option<(i32, i32)>,
+    click_mode: String,
+    normal_delay_ms: u64,
+    cps: f32,
+}
+
+impl Default for ClickTarget {
+    fn default() -> Self {
+        let profile = ProfileConfig::default();
+        Self {
+            rule: WindowMatchRule::default(),
+            child: None,
+            point: None,
+            click_mode: profile.click_mode,
+            normal_delay_ms: profile.normal_delay_ms,
+            cps: profile.cps,
+        }
+    }
+}
+
+/// A control inside a target window that receives the clicks instead of the
+/// window itself, at the centre of its own client area. It is found again by
+/// its class and its position among the window's descendants of that class;
+/// `text` is only shown to the user, since control text often changes.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+struct ChildControl {
+    class_name: String,
+    index: usize,
+    text: String,
+}
+
+/// A descendant of a window as listed by the control picker. `rect` is
+/// relative to the top-level window's client area.
+#[derive(Clone)]
+struct ChildInfo {
+    hwnd: HWND,
+    class_name: String,
+    text: String,
+    rect: RECT,
+    depth: usize,
+}
+
+/// Where a target is in its own schedule when the strategy is `Independent`,
+/// and whether it is being held in either case.
+#[derive(Default)]
+struct TargetSchedule {
+    next_due: Option<Instant>,
+    holding: bool,
+}
+
+/// A visible top-level window and what a `WindowMatchRule` can match it by.
+#[derive(Clone)]
+struct WindowInfo {
+    hwnd: HWND,
+    title: String,
+    class_name: String,
+    pid: u32,
+    exe_name: String,
+}
+
+/// The window a target last resolved to. Clicks reuse it until the window is
+/// destroyed or the target is edited, instead of enumerating every top-level
+/// window each time.
+#[derive(Default)]
+struct TargetCache {
+    target: Option<ClickTarget>,
+    hwnd: Option<HWND>,
+    point: Option<POINT>,
+    last_miss: Option<Instant>,
+}
+
+/// Loads `profile` whenever a foreground window matches. Empty criteria are
+/// ignored, and a rule with no criteria never matches.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+struct AutoProfileRule {
+    title_regex: String,
+    executable: String,
+    profile: String,
+    action: String,
+}
+
+/// A set of profiles passed between users, independent of anyone's config.
+#[derive(Serialize, Deserialize, Clone)]
+struct ProfileBundle {
+    bundle_version: u32,
+    profiles: Vec<ProfileConfig>,
+}
+
+/// A profile waiting to be imported. `name` starts out as a free name when
+/// the bundled name is already taken; `overwrite` replaces the existing
+/// profile instead.
+#[derive(Clone)]
+struct PendingImport {
+    profile: ProfileConfig,
+    name: String,
+    conflict: bool,
+    overwrite: bool,
+}
+
+const BUNDLE_VERSION: u32 = 3;
+const CONFIG_VERSION: u32 = 4;
+const CONFIG_SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+const CPS_RANGE: RangeInclusive<f32> = 1.0..=100.0;
+const DELAY_RANGE_MS: RangeInclusive<u64> = 1..=1000;
+const CLICK_MODES: &[&str] = &["Click", "Hold", "Humanized"];
+const CLICK_TYPES: &[&str] = &["LeftClick", "RightClick", "Space"];
+const ACTIVATION_STYLES: &[&str] = &["Toggle", "HoldToRun", "OneShot"];
+const AUTO_RULE_ACTIONS: &[&str] = &["", "Arm", "Disarm"];
+const TARGET_LOSS_ACTIONS: &[&str] = &["Stop", "Pause", "Global"];
+const CLICK_CONDITIONS: &[&str] = &["Always", "Foreground", "Background", "CursorInside"];
+const INJECTED_EVENT_WINDOW: Duration = Duration::from_millis(100);
+const INPUT_FAILURE_LIMIT: u32 = 5;
+const INPUT_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+const PAUSE_IDLE_RANGE_MS: RangeInclusive<u64> = 100..=60000;
+const FAILSAFE_ZONES: &[&str] = &["Off", "TopLeft", "TopRight", "BottomLeft", "BottomRight", "Rect"];
+const FAILSAFE_CORNER_PX: i32 = 2;
+const SESSION_LOG_LEN: usize = 500;
+const TARGET_STRATEGIES: &[&str] = &["RoundRobin", "AllAtOnce", "Independent"];
+const FOREGROUND_HISTORY_LEN: usize = 64;
+const TARGET_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+const WINDOW_MATCH_KINDS: &[&str] = &["ExactTitle", "TitleContains", "TitleRegex", "Class", "Executable"];
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+struct AppConfig {
+    version: u32,
+    active_profile: String,
+    profiles: Vec<ProfileConfig>,
+    start_hotkey: Vec<String>,
+    stop_hotkey: Vec<String>,
+    panic_hotkey: Vec<String>,
+    pick_target_hotkey: Vec<String>,
+    auto_rules: Vec<AutoProfileRule>,
+    failsafe: String,
+    failsafe_rect: [i32; 4],
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            active_profile: "Default".to_string(),
+            profiles: vec![ProfileConfig::default()],
+            start_hotkey: Vec::new(),
+            stop_hotkey: Vec::new(),
+            panic_hotkey: Vec::new(),
+            pick_target_hotkey: Vec::new(),
+            auto_rules: Vec::new(),
+            failsafe: "Off".to_string(),
+            failsafe_rect: [0, 0, 100, 100],
         }
     }
 }
 
 impl Default for PyladeClickerApp {
     fn default() -> Self {
-        let config = load_config();
-        
-        let hotkey_keys: Vec<Key> = config.hotkey.iter()
-            .filter_map(|s| string_to_key(s))
-            .collect();
-        
-        let click_mode = match config.click_mode.as_str() {
-            "Hold" => ClickMode::Hold,
-            "Humanized" => ClickMode::Humanized,
-            _ => ClickMode::Click,
-        };
+        let (mut config, config_error, corrected) = load_config();
+        if config.profiles.is_empty() {
+            config.profiles.push(ProfileConfig::default());
+        }
         
-        let click_type = match config.click_type.as_str() {
-            "RightClick" => ClickType::RightClick,
-            "Space" => ClickType::Space,
-            _ => ClickType::LeftClick,
-        };
+        let active_profile = config.profiles.iter()
+            .find(|p| p.name == config.active_profile)
+            .unwrap_or(&config.profiles[0])
+            .clone();
         
         let mut app = Self {
             clicking: Arc::new(AtomicBool::new(false)),
-            click_mode: Arc::new(Mutex::new(click_mode)),
-            click_type: Arc::new(Mutex::new(click_type)),
-            target_window: Arc::new(Mutex::new(None)),
+            click_mode: Arc::new(Mutex::new(ClickMode::Click)),
+            click_type: Arc::new(Mutex::new(ClickType::LeftClick)),
+            targets: Arc::new(Mutex::new(Vec::new())),
+            target_strategy: Arc::new(Mutex::new(TargetStrategy::RoundRobin)),
+            target_loss_action: Arc::new(Mutex::new(TargetLossAction::Pause)),
+            target_state: Arc::new(Mutex::new(TargetState::Found)),
+            click_condition: Arc::new(Mutex::new(ClickCondition::Always)),
+            condition_waiting: Arc::new(AtomicBool::new(false)),
+            pause_on_input: Arc::new(AtomicBool::new(false)),
+            pause_idle: Arc::new(Mutex::new(Duration::from_millis(1000))),
+            input_paused_until: Arc::new(Mutex::new(None)),
+            input_paused: Arc::new(AtomicBool::new(false)),
             windows: Vec::new(),
+            window_filter: String::new(),
+            window_icons: HashMap::new(),
+            foreground_history: Vec::new(),
             _last_click_time: Arc::new(Mutex::new(Instant::now())),
             _humanized_delay: Arc::new(Mutex::new(Duration::from_millis(100))),
-            normal_delay: Arc::new(Mutex::new(Duration::from_millis(config.normal_delay_ms))),
-            cps: Arc::new(Mutex::new(config.cps)),
-            hotkey: Arc::new(Mutex::new(if hotkey_keys.is_empty() { vec![Key::F6] } else { hotkey_keys })),
+            normal_delay: Arc::new(Mutex::new(Duration::from_millis(1000))),
+            cps: Arc::new(Mutex::new(10.0)),
+            hotkey: Arc::new(Mutex::new(Vec::new())),
             capturing_hotkey: Arc::new(AtomicBool::new(false)),
             listening_text: Arc::new(Mutex::new(String::new())),
             current_combination: Arc::new(Mutex::new(Vec::new())),
+            captured_hotkey: Arc::new(Mutex::new(None)),
             last_window_refresh: Arc::new(Mutex::new(Instant::now())),
             is_holding: Arc::new(AtomicBool::new(false)),
+            activation_style: Arc::new(Mutex::new(ActivationStyle::Toggle)),
+            one_shot_clicks: Arc::new(Mutex::new(10)),
+            one_shot_remaining: Arc::new(Mutex::new(None)),
+            start_hotkey: Arc::new(Mutex::new(parse_hotkey(&config.start_hotkey))),
+            stop_hotkey: Arc::new(Mutex::new(parse_hotkey(&config.stop_hotkey))),
+            panic_hotkey: Arc::new(Mutex::new(parse_hotkey(&config.panic_hotkey))),
+            pick_target_hotkey: Arc::new(Mutex::new(parse_hotkey(&config.pick_target_hotkey))),
+            pick_point: Arc::new(AtomicBool::new(true)),
+            input_failures: Arc::new(Mutex::new(0)),
+            input_error: Arc::new(Mutex::new(None)),
+            failsafe_zone: Arc::new(Mutex::new(failsafe_zone_from_str(&config.failsafe))),
+            failsafe_rect: Arc::new(Mutex::new(config.failsafe_rect)),
+            hotkeys_armed: Arc::new(AtomicBool::new(true)),
+            capture_slot: Arc::new(Mutex::new(HotkeySlot::Activation)),
+            profiles: Arc::new(Mutex::new(config.profiles.clone())),
+            active_profile: Arc::new(Mutex::new(active_profile.name.clone())),
+            profile_switch_hotkey: Arc::new(Mutex::new(Vec::new())),
+            profile_name_edit: active_profile.name.clone(),
+            profile_name_edit_for: active_profile.name.clone(),
+            auto_rules: Arc::new(Mutex::new(config.auto_rules.clone())),
+            clicker_armed: Arc::new(AtomicBool::new(true)),
+            last_foreground: None,
+            disarmed_by_rule: false,
+            config_error: Arc::new(Mutex::new(config_error)),
+            save_requested: Arc::new(Mutex::new(None)),
+            config_save_error: Arc::new(Mutex::new(None)),
+            export_selection: Vec::new(),
+            bundle_path: get_config_path().with_file_name("profiles-bundle.json").display().to_string(),
+            import_text: String::new(),
+            pending_import: Vec::new(),
+            bundle_status: None,
+            control_picker: None,
+            config_mtime: Arc::new(Mutex::new(config_file_mtime())),
+            external_config: Arc::new(Mutex::new(None)),
+            external_config_error: Arc::new(Mutex::new(None)),
+            session_log: Arc::new(Mutex::new(Vec::new())),
+            session_start: Instant::now(),
         };
         
+        app.apply_profile(&active_profile);
         app.refresh_windows();
         
+        // Write the corrections back so the same problems aren't reported,
+        // and backed up again, on every launch
+        if corrected {
+            app.save_current_config();
+        }
+        
         app
     }
 }
 
-fn vk_to_key(vk: u32) -> Option<Key> {
-    match vk {
-        0x70 => Some(Key::F1),
-        0x71 => Some(Key::F2),
-        0x72 => Some(Key::F3),
-        0x73 => Some(Key::F4),
-        0x74 => Some(Key::F5),
-        0x75 => Some(Key::F6),
-        0x76 => Some(Key::F7),
-        0x77 => Some(Key::F8),
-        0x78 => Some(Key::F9),
-        0x79 => Some(Key::F10),
-        0x7A => Some(Key::F11),
-        0x7B => Some(Key::F12),
-        0x20 => Some(Key::Space),
-        0x0D => Some(Key::Return),
-        0x1B => Some(Key::Escape),
-        0x09 => Some(Key::Tab),
-        0x14 => Some(Key::CapsLock),
-        0xA0 => Some(Key::ShiftLeft),
-        0xA1 => Some(Key::ShiftRight),
-        0xA2 => Some(Key::ControlLeft),
-        0xA3 => Some(Key::ControlRight),
-        0x12 => Some(Key::Alt),
-        0xA5 => Some(Key::AltGr),
-        _ => None,
+/// Every named `rdev::Key` with its display name and Windows virtual-key code.
+/// The display name is what gets written to the config file.
+const KEY_TABLE: &[(Key, &str, Option<u32>)] = &[
+    (Key::F1, "F1", Some(0x70)),
+    (Key::F2, "F2", Some(0x71)),
+    (Key::F3, "F3", Some(0x72)),
+    (Key::F4, "F4", Some(0x73)),
+    (Key::F5, "F5", Some(0x74)),
+    (Key::F6, "F6", Some(0x75)),
+    (Key::F7, "F7", Some(0x76)),
+    (Key::F8, "F8", Some(0x77)),
+    (Key::F9, "F9", Some(0x78)),
+    (Key::F10, "F10", Some(0x79)),
+    (Key::F11, "F11", Some(0x7A)),
+    (Key::F12, "F12", Some(0x7B)),
+    (Key::Home, "Home", Some(0x24)),
+    (Key::End, "End", Some(0x23)),
+    (Key::PageUp, "Page Up", Some(0x21)),
+    (Key::PageDown, "Page Down", Some(0x22)),
+    (Key::Insert, "Insert", Some(0x2D)),
+    (Key::Delete, "Delete", Some(0x2E)),
+    (Key::UpArrow, "Up", Some(0x26)),
+    (Key::DownArrow, "Down", Some(0x28)),
+    (Key::LeftArrow, "Left", Some(0x25)),
+    (Key::RightArrow, "Right", Some(0x27)),
+    (Key::Space, "Space", Some(0x20)),
+    (Key::Return, "Enter", Some(0x0D)),
+    (Key::Escape, "Escape", Some(0x1B)),
+    (Key::Tab, "Tab", Some(0x09)),
+    (Key::Backspace, "Backspace", Some(0x08)),
+    (Key::CapsLock, "Caps Lock", Some(0x14)),
+    (Key::PrintScreen, "Print Screen", Some(0x2C)),
+    (Key::ScrollLock, "Scroll Lock", Some(0x91)),
+    (Key::Pause, "Pause", Some(0x13)),
+    (Key::NumLock, "Num Lock", Some(0x90)),
+    (Key::ShiftLeft, "Left Shift", Some(0xA0)),
+    (Key::ShiftRight, "Right Shift", Some(0xA1)),
+    (Key::ControlLeft, "Left Ctrl", Some(0xA2)),
+    (Key::ControlRight, "Right Ctrl", Some(0xA3)),
+    (Key::Alt, "Alt", Some(0xA4)),
+    (Key::AltGr, "Alt Gr", Some(0xA5)),
+    (Key::MetaLeft, "Left Win", Some(0x5B)),
+    (Key::MetaRight, "Right Win", Some(0x5C)),
+    (Key::Function, "Fn", None),
+    (Key::KeyA, "A", Some(0x41)),
+    (Key::KeyB, "B", Some(0x42)),
+    (Key::KeyC, "C", Some(0x43)),
+    (Key::KeyD, "D", Some(0x44)),
+    (Key::KeyE, "E", Some(0x45)),
+    (Key::KeyF, "F", Some(0x46)),
+    (Key::KeyG, "G", Some(0x47)),
+    (Key::KeyH, "H", Some(0x48)),
+    (Key::KeyI, "I", Some(0x49)),
+    (Key::KeyJ, "J", Some(0x4A)),
+    (Key::KeyK, "K", Some(0x4B)),
+    (Key::KeyL, "L", Some(0x4C)),
+    (Key::KeyM, "M", Some(0x4D)),
+    (Key::KeyN, "N", Some(0x4E)),
+    (Key::KeyO, "O", Some(0x4F)),
+    (Key::KeyP, "P", Some(0x50)),
+    (Key::KeyQ, "Q", Some(0x51)),
+    (Key::KeyR, "R", Some(0x52)),
+    (Key::KeyS, "S", Some(0x53)),
+    (Key::KeyT, "T", Some(0x54)),
+    (Key::KeyU, "U", Some(0x55)),
+    (Key::KeyV, "V", Some(0x56)),
+    (Key::KeyW, "W", Some(0x57)),
+    (Key::KeyX, "X", Some(0x58)),
+    (Key::KeyY, "Y", Some(0x59)),
+    (Key::KeyZ, "Z", Some(0x5A)),
+    (Key::Num0, "0", Some(0x30)),
+    (Key::Num1, "1", Some(0x31)),
+    (Key::Num2, "2", Some(0x32)),
+    (Key::Num3, "3", Some(0x33)),
+    (Key::Num4, "4", Some(0x34)),
+    (Key::Num5, "5", Some(0x35)),
+    (Key::Num6, "6", Some(0x36)),
+    (Key::Num7, "7", Some(0x37)),
+    (Key::Num8, "8", Some(0x38)),
+    (Key::Num9, "9", Some(0x39)),
+    (Key::Minus, "Minus", Some(0xBD)),
+    (Key::Equal, "Equals", Some(0xBB)),
+    (Key::LeftBracket, "Left Bracket", Some(0xDB)),
+    (Key::RightBracket, "Right Bracket", Some(0xDD)),
+    (Key::SemiColon, "Semicolon", Some(0xBA)),
+    (Key::Quote, "Quote", Some(0xDE)),
+    (Key::BackQuote, "Backtick", Some(0xC0)),
+    (Key::BackSlash, "Backslash", Some(0xDC)),
+    (Key::IntlBackslash, "Intl Backslash", Some(0xE2)),
+    (Key::Comma, "Comma", Some(0xBC)),
+    (Key::Dot, "Period", Some(0xBE)),
+    (Key::Slash, "Slash", Some(0xBF)),
+    (Key::Kp0, "Numpad 0", Some(0x60)),
+    (Key::Kp1, "Numpad 1", Some(0x61)),
+    (Key::Kp2, "Numpad 2", Some(0x62)),
+    (Key::Kp3, "Numpad 3", Some(0x63)),
+    (Key::Kp4, "Numpad 4", Some(0x64)),
+    (Key::Kp5, "Numpad 5", Some(0x65)),
+    (Key::Kp6, "Numpad 6", Some(0x66)),
+    (Key::Kp7, "Numpad 7", Some(0x67)),
+    (Key::Kp8, "Numpad 8", Some(0x68)),
+    (Key::Kp9, "Numpad 9", Some(0x69)),
+    (Key::KpReturn, "Numpad Enter", None),
+    (Key::KpMinus, "Numpad Minus", Some(0x6D)),
+    (Key::KpPlus, "Numpad Plus", Some(0x6B)),
+    (Key::KpMultiply, "Numpad Multiply", Some(0x6A)),
+    (Key::KpDivide, "Numpad Divide", Some(0x6F)),
+    (Key::KpDelete, "Numpad Delete", Some(0x6E)),
+];
+
+fn key_to_string(key: &Key) -> String {
+    if let Key::Unknown(code) = key {
+        return format!("Unknown({})", code);
     }
+    
+    KEY_TABLE.iter()
+        .find(|entry| entry.0 == *key)
+        .map(|entry| entry.1.to_string())
+        .unwrap_or_else(|| format!("{:?}", key))
 }
 
-fn key_to_string(key: &Key) -> String {
-    match key {
-        Key::F1 => "F1".to_string(),
-        Key::F2 => "F2".to_string(),
-        Key::F3 => "F3".to_string(),
-        Key::F4 => "F4".to_string(),
-        Key::F5 => "F5".to_string(),
-        Key::F6 => "F6".to_string(),
-        Key::F7 => "F7".to_string(),
-        Key::F8 => "F8".to_string(),
-        Key::F9 => "F9".to_string(),
-        Key::F10 => "F10".to_string(),
-        Key::F11 => "F11".to_string(),
-        Key::F12 => "F12".to_string(),
-        
-        Key::Home => "Home".to_string(),
-        Key::End => "End".to_string(),
-        Key::PageUp => "Page Up".to_string(),
-        Key::PageDown => "Page Down".to_string(),
-        Key::Insert => "Insert".to_string(),
-        Key::Delete => "Delete".to_string(),
-        Key::UpArrow => "Up".to_string(),
-        Key::DownArrow => "Down".to_string(),
-        Key::LeftArrow => "Left".to_string(),
-        Key::RightArrow => "Right".to_string(),
-        
-        Key::Space => "Space".to_string(),
-        Key::Return => "Enter".to_string(),
-        Key::Escape => "Escape".to_string(),
-        Key::Tab => "Tab".to_string(),
-        Key::Backspace => "Backspace".to_string(),
-        Key::CapsLock => "Caps Lock".to_string(),
-        
-        Key::ShiftLeft => "Left Shift".to_string(),
-        Key::ShiftRight => "Right Shift".to_string(),
-        Key::ControlLeft => "Left Ctrl".to_string(),
-        Key::ControlRight => "Right Ctrl".to_string(),
-        Key::Alt => "Alt".to_string(),
-        Key::AltGr => "Alt Gr".to_string(),
-        
-        _ => format!("{:?}", key),
-    }
-}
-
-fn combination_to_string(combination: &[Key]) -> String {
+fn string_to_key(s: &str) -> Option<Key> {
+    if let Some(entry) = KEY_TABLE.iter().find(|entry| entry.1 == s) {
+        return Some(entry.0);
+    }
+    
+    // Older configs stored some keys by their rdev variant name ("PageUp", "ShiftLeft").
+    if let Some(entry) = KEY_TABLE.iter().find(|entry| format!("{:?}", entry.0) == s) {
+        return Some(entry.0);
+    }
+    
+    s.strip_prefix("Unknown(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .and_then(|code| code.parse().ok())
+        .map(Key::Unknown)
+}
+
+fn key_to_vk(key: &Key) -> Option<u32> {
+    if let Key::Unknown(code) = key {
+        return Some(*code);
+    }
+    
+    KEY_TABLE.iter().find(|entry| entry.0 == *key).and_then(|entry| entry.2)
+}
+
+fn hotkey_input_to_string(input: &HotkeyInput) -> String {
+    match input {
+        HotkeyInput::Key(key) => key_to_string(key),
+        HotkeyInput::Button(Button::Middle) => "Middle Mouse".to_string(),
+        HotkeyInput::Button(Button::Unknown(1)) => "Mouse 4".to_string(),
+        HotkeyInput::Button(Button::Unknown(2)) => "Mouse 5".to_string(),
+        HotkeyInput::Button(button) => format!("{:?}", button),
+    }
+}
+
+fn string_to_hotkey_input(s: &str) -> Option<HotkeyInput> {
+    match s {
+        "Middle Mouse" => Some(HotkeyInput::Button(Button::Middle)),
+        "Mouse 4" => Some(HotkeyInput::Button(Button::Unknown(1))),
+        "Mouse 5" => Some(HotkeyInput::Button(Button::Unknown(2))),
+        _ => string_to_key(s).map(HotkeyInput::Key),
+    }
+}
+
+fn parse_hotkey(strings: &[String]) -> Vec<HotkeyInput> {
+    strings.iter().filter_map(|s| string_to_hotkey_input(s)).collect()
+}
+
+fn hotkey_to_strings(hotkey: &[HotkeyInput]) -> Vec<String> {
+    hotkey.iter().map(hotkey_input_to_string).collect()
+}
+
+fn combination_to_string(combination: &[HotkeyInput]) -> String {
     if combination.is_empty() {
         return "Press keys...".to_string();
     }
     
-    let key_strings: Vec<String> = combination.iter().map(key_to_string).collect();
+    let key_strings: Vec<String> = combination.iter().map(hotkey_input_to_string).collect();
     key_strings.join(" + ")
 }
 
-fn start_hotkey_toggle_listener(hotkey: Arc<Mutex<Vec<Key>>>, clicking: Arc<AtomicBool>) {
-    std::thread::spawn(move || {
-        thread::sleep(Duration::from_millis(200));
-        let callback = move |event: Event| {
-            if let EventType::KeyPress(key) = event.event_type {
-                let current_hotkey = hotkey.lock().unwrap();
-                
-                if current_hotkey.len() == 1 {
-                    if let Some(&hotkey_key) = current_hotkey.first() {
-                        if key == hotkey_key {
-                            clicking.store(!clicking.load(Ordering::SeqCst), Ordering::SeqCst);
-                        }
-                    }
-                }
-                else if current_hotkey.contains(&key) && current_hotkey.len() > 1 {
-                    clicking.store(!clicking.load(Ordering::SeqCst), Ordering::SeqCst);
-                }
-            }
-        };
-        
-        if let Err(error) = listen(callback) {
-            eprintln!("Failed to start hotkey toggle listener: {:?}", error);
-        }
-    });
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ChordEvent {
+    Pressed,
+    Released,
 }
 
-fn start_clicking_thread(
-    clicking: Arc<AtomicBool>,
-    click_mode: Arc<Mutex<ClickMode>>,
-    click_type: Arc<Mutex<ClickType>>,
-    target_window: Arc<Mutex<Option<String>>>,
-    _last_click_time: Arc<Mutex<Instant>>,
-    _humanized_delay: Arc<Mutex<Duration>>,
-    normal_delay: Arc<Mutex<Duration>>,
-    cps: Arc<Mutex<f32>>,
-    is_holding: Arc<AtomicBool>,
-) {
-    std::thread::spawn(move || {
-        let mut rng = rand::thread_rng();
-        
-        loop {
-            if clicking.load(Ordering::SeqCst) {
-                let mode = click_mode.lock().unwrap().clone();
-                let click_type = click_type.lock().unwrap().clone();
-                let target = target_window.lock().unwrap().clone();
-                
-                match mode {
-                    ClickMode::Click => {
-                        let delay = *normal_delay.lock().unwrap();
-                        perform_click(&click_type, &target);
-                        thread::sleep(delay);
-                    }
-                    ClickMode::Hold => {
-                        if !is_holding.load(Ordering::SeqCst) {
-                            perform_hold(&click_type, &target);
-                            is_holding.store(true, Ordering::SeqCst);
-                        }
-                        thread::sleep(Duration::from_millis(10));
-                    }
-                    ClickMode::Humanized => {
-                        let cps_value = *cps.lock().unwrap();
-                        
-                        if cps_value > 50.0 {
-                            drag_click_burst(&target, cps_value, &mut rng, &click_type);
-                            
-                            let break_time = Duration::from_millis(rng.gen_range(450..=550));
-                            thread::sleep(break_time);
-                        } else {
-                            perform_click(&click_type, &target);
-                            
-                            let delay = calculate_humanized_delay(cps_value, &mut rng);
-                            thread::sleep(delay);
-                        }
-                    }
-                }
-            } else {
-                if is_holding.load(Ordering::SeqCst) {
-                    let target = target_window.lock().unwrap().clone();
-                    let click_type = click_type.lock().unwrap().clone();
-                    perform_release(&click_type, &target);
-                    is_holding.store(false, Ordering::SeqCst);
-                }
-                thread::sleep(Duration::from_millis(10));
-            }
-        }
-    });
+#[derive(Default)]
+struct ChordDetector {
+    pressed: Vec<HotkeyInput>,
+    active: bool,
 }
 
-fn calculate_humanized_delay(cps: f32, rng: &mut impl rand::Rng) -> Duration {
-    let base_delay_ms = 1000.0 / cps;
-    let variation = if cps > 20.0 {
-        rng.gen_range(-3.0..=3.0)
-    } else if cps > 10.0 {
-        rng.gen_range(-5.0..=5.0)
-    } else {
-        rng.gen_range(-10.0..=10.0)
-    };
-    let final_delay = (base_delay_ms + variation).max(1.0);
-    
-    
-    Duration::from_millis(final_delay as u64)
+/// Maps a hook event onto a hotkey input and whether it was pressed. Left and
+/// right buttons are never hotkey inputs since those are what the clicker
+/// itself synthesizes.
+fn hotkey_input_from_event(event_type: &EventType) -> Option<(HotkeyInput, bool)> {
+    match *event_type {
+        EventType::KeyPress(key) => Some((HotkeyInput::Key(key), true)),
+        EventType::KeyRelease(key) => Some((HotkeyInput::Key(key), false)),
+        EventType::ButtonPress(Button::Left | Button::Right) => None,
+        EventType::ButtonRelease(Button::Left | Button::Right) => None,
+        EventType::ButtonPress(button) => Some((HotkeyInput::Button(button), true)),
+        EventType::ButtonRelease(button) => Some((HotkeyInput::Button(button), false)),
+        _ => None,
+    }
 }
 
-fn drag_click_burst(target: &Option<String>, target_cps: f32, rng: &mut impl rand::Rng, click_type: &ClickType) {
-    let base_burst_size = (target_cps * 0.5) as usize;
-    let burst_count = rng.gen_range((base_burst_size.saturating_sub(5))..=(base_burst_size + 5));
-    
-    let burst_delay = Duration::from_micros(rng.gen_range(500..=1500));
-    
-    for i in 0..burst_count {
-        perform_click(click_type, target);
-        
-        if i < burst_count - 1 {
-            thread::sleep(burst_delay);
-        }
-    }
+/// Whether an input is physically down. Called from the hook, this is the
+/// state from before the event being handled, so a fresh press reads as up
+/// and an auto-repeat as down. Inputs without a virtual-key code count as down.
+fn hotkey_input_is_down(input: &HotkeyInput) -> bool {
+    let vk = match input {
+        HotkeyInput::Key(key) => key_to_vk(key).map(|vk| vk as i32),
+        HotkeyInput::Button(Button::Middle) => Some(VK_MBUTTON.0 as i32),
+        HotkeyInput::Button(Button::Unknown(1)) => Some(VK_XBUTTON1.0 as i32),
+        HotkeyInput::Button(Button::Unknown(2)) => Some(VK_XBUTTON2.0 as i32),
+        HotkeyInput::Button(_) => None,
+    };
+    vk.is_none_or(|vk| unsafe { GetAsyncKeyState(vk) } < 0)
 }
 
-fn perform_click(click_type: &ClickType, target: &Option<String>) {
-    match click_type {
-        ClickType::LeftClick => {
-            if let Some(ref window_title) = target {
-                click_target_window(window_title);
-            } else {
-                simulate(&EventType::ButtonPress(Button::Left)).unwrap();
-                thread::sleep(Duration::from_millis(1));
-                simulate(&EventType::ButtonRelease(Button::Left)).unwrap();
+impl ChordDetector {
+    /// Tracks `event_type` against `chord`, which fires once all of its inputs
+    /// are held, whatever else is held with them. On every press, inputs that
+    /// `is_down` reports as up are forgotten first, since the hook misses
+    /// releases while the session is locked or a secure desktop is shown.
+    fn handle(
+        &mut self,
+        chord: &[HotkeyInput],
+        event_type: &EventType,
+        is_down: impl Fn(&HotkeyInput) -> bool,
+    ) -> Option<ChordEvent> {
+        let (input, pressed) = hotkey_input_from_event(event_type)?;
+        
+        if pressed {
+            self.pressed.retain(|k| is_down(k));
+            let was_active = self.active;
+            self.active &= chord.iter().all(|k| self.pressed.contains(k));
+            
+            if !self.pressed.contains(&input) {
+                self.pressed.push(input);
             }
-        }
-        ClickType::RightClick => {
-            if let Some(ref window_title) = target {
-                right_click_target_window(window_title);
-            } else {
-                simulate(&EventType::ButtonPress(Button::Right)).unwrap();
-                thread::sleep(Duration::from_millis(1));
-                simulate(&EventType::ButtonRelease(Button::Right)).unwrap();
+            
+            let chord_held = !chord.is_empty() && chord.iter().all(|k| self.pressed.contains(k));
+            
+            if chord_held && !self.active {
+                self.active = true;
+                return Some(ChordEvent::Pressed);
             }
-        }
-        ClickType::Space => {
-            if let Some(ref window_title) = target {
-                space_target_window(window_title);
-            } else {
-                simulate(&EventType::KeyPress(Key::Space)).unwrap();
-                thread::sleep(Duration::from_millis(1));
-                simulate(&EventType::KeyRelease(Key::Space)).unwrap();
+            if was_active && !self.active {
+                return Some(ChordEvent::Released);
+            }
+            None
+        } else {
+            self.pressed.retain(|&k| k != input);
+            
+            if self.active && chord.contains(&input) {
+                self.active = false;
+                return Some(ChordEvent::Released);
             }
+            None
         }
     }
 }
 
-fn perform_hold(click_type: &ClickType, target: &Option<String>) {
-    match click_type {
-        ClickType::LeftClick => {
-            if let Some(ref window_title) = target {
-                hold_target_window(window_title);
-            } else {
-                simulate(&EventType::ButtonPress(Button::Left)).unwrap();
-            }
-        }
-        ClickType::RightClick => {
-            if let Some(ref window_title) = target {
-                right_hold_target_window(window_title);
+fn start_clicking(
+    clicking: &AtomicBool,
+    activation_style: &Mutex<ActivationStyle>,
+    one_shot_clicks: &Mutex<u32>,
+    one_shot_remaining: &Mutex<Option<u32>>,
+) {
+    let style = activation_style.lock().unwrap().clone();
+    *one_shot_remaining.lock().unwrap() = match style {
+        ActivationStyle::OneShot => Some(*one_shot_clicks.lock().unwrap()),
+        _ => None,
+    };
+    clicking.store(true, Ordering::SeqCst);
+}
+
+fn on_hotkey_pressed(
+    clicking: &AtomicBool,
+    activation_style: &Mutex<ActivationStyle>,
+    one_shot_clicks: &Mutex<u32>,
+    one_shot_remaining: &Mutex<Option<u32>>,
+) {
+    let style = activation_style.lock().unwrap().clone();
+    match style {
+        ActivationStyle::Toggle => {
+            if clicking.load(Ordering::SeqCst) {
+                clicking.store(false, Ordering::SeqCst);
             } else {
-                simulate(&EventType::ButtonPress(Button::Right)).unwrap();
+                start_clicking(clicking, activation_style, one_shot_clicks, one_shot_remaining);
             }
         }
-        ClickType::Space => {
-            if let Some(ref window_title) = target {
-                space_hold_target_window(window_title);
-            } else {
-                simulate(&EventType::KeyPress(Key::Space)).unwrap();
+        ActivationStyle::HoldToRun | ActivationStyle::OneShot => {
+            if !clicking.load(Ordering::SeqCst) {
+                start_clicking(clicking, activation_style, one_shot_clicks, one_shot_remaining);
             }
         }
     }
 }
 
-fn perform_release(click_type: &ClickType, target: &Option<String>) {
-    match click_type {
-        ClickType::LeftClick => {
-            if let Some(ref window_title) = target {
-                release_target_window(window_title);
-            } else {
-                simulate(&EventType::ButtonRelease(Button::Left)).unwrap();
-            }
-        }
-        ClickType::RightClick => {
-            if let Some(ref window_title) = target {
-                right_release_target_window(window_title);
-            } else {
-                simulate(&EventType::ButtonRelease(Button::Right)).unwrap();
-            }
-        }
-        ClickType::Space => {
-            if let Some(ref window_title) = target {
-                space_release_target_window(window_title);
-            } else {
-                simulate(&EventType::KeyRelease(Key::Space)).unwrap();
-            }
-        }
+fn on_hotkey_released(clicking: &AtomicBool, activation_style: &Mutex<ActivationStyle>) {
+    if *activation_style.lock().unwrap() == ActivationStyle::HoldToRun {
+        clicking.store(false, Ordering::SeqCst);
     }
 }
 
-fn click_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
+fn record_one_shot_clicks(one_shot_remaining: &Mutex<Option<u32>>, clicking: &AtomicBool, performed: u32) {
+    let mut remaining = one_shot_remaining.lock().unwrap();
+    if let Some(left) = *remaining {
+        let left = left.saturating_sub(performed);
+        if left == 0 {
+            *remaining = None;
+            clicking.store(false, Ordering::SeqCst);
+        } else {
+            *remaining = Some(left);
         }
-        
-        if let Some(hwnd) = found_hwnd {
-            let mut client_rect = RECT::default();
-            GetClientRect(hwnd, &mut client_rect);
-            let client_x = (client_rect.left + client_rect.right) / 2;
-            let client_y = (client_rect.top + client_rect.bottom) / 2;
-            let lparam = ((client_y as u32) << 16) | (client_x as u32);
-            
-            PostMessageW(hwnd, WM_LBUTTONDOWN, WPARAM(1), LPARAM(lparam as isize));
-            thread::sleep(Duration::from_millis(1));
-            PostMessageW(hwnd, WM_LBUTTONUP, WPARAM(0), LPARAM(lparam as isize));
+    }
+}
+
+/// Events this process has injected, kept just long enough for the hook to
+/// see them come back.
+static INJECTED_EVENTS: Mutex<Vec<(EventType, Instant)>> = Mutex::new(Vec::new());
+
+/// Simulates `event_type`, first recording it so the hotkey listener can tell
+/// it apart from real input.
+fn inject(event_type: &EventType) -> Result<(), rdev::SimulateError> {
+    INJECTED_EVENTS.lock().unwrap().push((*event_type, Instant::now()));
+    simulate(event_type)
+}
+
+/// Whether a hooked event is one of ours coming back, consuming the record if
+/// so.
+fn take_injected(event_type: &EventType) -> bool {
+    let mut injected = INJECTED_EVENTS.lock().unwrap();
+    injected.retain(|(_, at)| at.elapsed() < INJECTED_EVENT_WINDOW);
+    match injected.iter().position(|(injected_type, _)| injected_type == event_type) {
+        Some(index) => {
+            injected.remove(index);
+            true
         }
+        None => false,
     }
 }
 
-fn right_click_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            let mut client_rect = RECT::default();
-            GetClientRect(hwnd, &mut client_rect);
-            let client_x = (client_rect.left + client_rect.right) / 2;
-            let client_y = (client_rect.top + client_rect.bottom) / 2;
-            let lparam = ((client_y as u32) << 16) | (client_x as u32);
-            
-            PostMessageW(hwnd, WM_RBUTTONDOWN, WPARAM(1), LPARAM(lparam as isize));
-            thread::sleep(Duration::from_millis(1));
-            PostMessageW(hwnd, WM_RBUTTONUP, WPARAM(0), LPARAM(lparam as isize));
-        }
-    }
+/// An input this process has pressed and not yet released, stored as what
+/// releases it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum HeldInput {
+    Global(EventType),
+    Window { hwnd: isize, message: u32, wparam: usize, lparam: isize },
 }
 
-fn space_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            PostMessageW(hwnd, WM_KEYDOWN, WPARAM(0x20), LPARAM(0));
-            thread::sleep(Duration::from_millis(1));
-            PostMessageW(hwnd, WM_KEYUP, WPARAM(0x20), LPARAM(0));
+impl HeldInput {
+    /// Whether both release the same button or key. Window releases may be
+    /// posted at a different point than the press was.
+    fn same_input(&self, other: &HeldInput) -> bool {
+        match (self, other) {
+            (HeldInput::Global(a), HeldInput::Global(b)) => a == b,
+            (
+                HeldInput::Window { hwnd, message, .. },
+                HeldInput::Window { hwnd: other_hwnd, message: other_message, .. },
+            ) => hwnd == other_hwnd && message == other_message,
+            _ => false,
         }
     }
-}
-
-fn hold_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
+    
+    fn send(&self) {
+        match *self {
+            HeldInput::Global(event_type) => {
+                let _ = inject(&event_type);
             }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            let mut client_rect = RECT::default();
-            GetClientRect(hwnd, &mut client_rect);
-            let client_x = (client_rect.left + client_rect.right) / 2;
-            let client_y = (client_rect.top + client_rect.bottom) / 2;
-            let lparam = ((client_y as u32) << 16) | (client_x as u32);
-            
-            PostMessageW(hwnd, WM_LBUTTONDOWN, WPARAM(1), LPARAM(lparam as isize));
+            HeldInput::Window { hwnd, message, wparam, lparam } => unsafe {
+                PostMessageW(HWND(hwnd), message, WPARAM(wparam), LPARAM(lparam));
+            },
         }
     }
 }
 
-fn release_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            let mut client_rect = RECT::default();
-            GetClientRect(hwnd, &mut client_rect);
-            let client_x = (client_rect.left + client_rect.right) / 2;
-            let client_y = (client_rect.top + client_rect.bottom) / 2;
-            let lparam = ((client_y as u32) << 16) | (client_x as u32);
-            
-            PostMessageW(hwnd, WM_LBUTTONUP, WPARAM(0), LPARAM(lparam as isize));
-        }
-    }
+/// The inputs held down at the moment, in the order they were pressed.
+#[derive(Default)]
+struct InputTracker {
+    held: Vec<HeldInput>,
 }
 
-fn right_hold_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            let mut client_rect = RECT::default();
-            GetClientRect(hwnd, &mut client_rect);
-            let client_x = (client_rect.left + client_rect.right) / 2;
-            let client_y = (client_rect.top + client_rect.bottom) / 2;
-            let lparam = ((client_y as u32) << 16) | (client_x as u32);
-            
-            PostMessageW(hwnd, WM_RBUTTONDOWN, WPARAM(1), LPARAM(lparam as isize));
-        }
+impl InputTracker {
+    const fn new() -> Self {
+        InputTracker { held: Vec::new() }
     }
-}
-
-fn right_release_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            let mut client_rect = RECT::default();
-            GetClientRect(hwnd, &mut client_rect);
-            let client_x = (client_rect.left + client_rect.right) / 2;
-            let client_y = (client_rect.top + client_rect.bottom) / 2;
-            let lparam = ((client_y as u32) << 16) | (client_x as u32);
-            
-            PostMessageW(hwnd, WM_RBUTTONUP, WPARAM(0), LPARAM(lparam as isize));
+    
+    fn press(&mut self, release: HeldInput) {
+        if !self.held.iter().any(|input| input.same_input(&release)) {
+            self.held.push(release);
         }
     }
-}
-
-fn space_hold_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            PostMessageW(hwnd, WM_KEYDOWN, WPARAM(0x20), LPARAM(0));
-        }
+    
+    fn release(&mut self, release: HeldInput) {
+        self.held.retain(|input| !input.same_input(&release));
     }
-}
-
-fn space_release_target_window(window_title: &str) {
-    unsafe {
-        let mut found_hwnd = None;
-        let mut window_data = Vec::new();
-        
-        EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-        );
-        
-        for (hwnd, title) in window_data.iter() {
-            if title == window_title {
-                found_hwnd = Some(*hwnd);
-                break;
-            }
-        }
-        
-        if let Some(hwnd) = found_hwnd {
-            PostMessageW(hwnd, WM_KEYUP, WPARAM(0x20), LPARAM(0));
+    
+    /// Passes every held input's release to `send`, most recent first, and
+    /// forgets them.
+    fn release_all(&mut self, mut send: impl FnMut(&HeldInput)) {
+        for input in std::mem::take(&mut self.held).iter().rev() {
+            send(input);
         }
     }
 }
 
+/// Everything currently held down, so it can be released from anywhere: on
+/// shutdown, from the panic hook and when the targets change under a hold.
+static HELD_INPUTS: Mutex<InputTracker> = Mutex::new(InputTracker::new());
 
+fn held_inputs() -> std::sync::MutexGuard<'static, InputTracker> {
+    HELD_INPUTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
+fn track_press(release: HeldInput) {
+    held_inputs().press(release);
+}
 
+fn track_release(release: HeldInput) {
+    held_inputs().release(release);
+}
 
+/// Releases every input still held down, wherever it was pressed.
+fn release_tracked_inputs() {
+    let mut held = std::mem::take(&mut *held_inputs());
+    held.release_all(HeldInput::send);
+}
 
+fn failsafe_zone_from_str(s: &str) -> FailsafeZone {
+    match s {
+        "TopLeft" => FailsafeZone::TopLeft,
+        "TopRight" => FailsafeZone::TopRight,
+        "BottomLeft" => FailsafeZone::BottomLeft,
+        "BottomRight" => FailsafeZone::BottomRight,
+        "Rect" => FailsafeZone::Rect,
+        _ => FailsafeZone::Off,
+    }
+}
 
+fn failsafe_zone_to_str(zone: FailsafeZone) -> &'static str {
+    match zone {
+        FailsafeZone::Off => "Off",
+        FailsafeZone::TopLeft => "TopLeft",
+        FailsafeZone::TopRight => "TopRight",
+        FailsafeZone::BottomLeft => "BottomLeft",
+        FailsafeZone::BottomRight => "BottomRight",
+        FailsafeZone::Rect => "Rect",
+    }
+}
 
+/// Whether the cursor at `(x, y)` is inside the failsafe zone. Corners are
+/// those of the primary monitor; `rect` is `[left, top, right, bottom]` in
+/// screen coordinates.
+fn in_failsafe_zone(zone: FailsafeZone, rect: [i32; 4], x: f64, y: f64) -> bool {
+    let (x, y) = (x as i32, y as i32);
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    let near_start = |value: i32| (0..=FAILSAFE_CORNER_PX).contains(&value);
+    let near_end = |value: i32, size: i32| (size - 1 - FAILSAFE_CORNER_PX..size).contains(&value);
+    
+    match zone {
+        FailsafeZone::Off => false,
+        FailsafeZone::TopLeft => near_start(x) && near_start(y),
+        FailsafeZone::TopRight => near_end(x, width) && near_start(y),
+        FailsafeZone::BottomLeft => near_start(x) && near_end(y, height),
+        FailsafeZone::BottomRight => near_end(x, width) && near_end(y, height),
+        FailsafeZone::Rect => (rect[0]..rect[2]).contains(&x) && (rect[1]..rect[3]).contains(&y),
+    }
+}
 
+/// Releases whatever the clicking thread is holding on `targets`, which must
+/// be where it was pressed. Anything the targets no longer account for, such
+/// as a window that has since closed, is released from the tracker.
+fn release_held(app: &PyladeClickerApp, click_type: &ClickType, targets: &[ClickTarget], caches: &mut [TargetCache]) {
+    if app.is_holding.swap(false, Ordering::SeqCst) {
+        let all: Vec<usize> = (0..targets.len()).collect();
+        if let Err(error) = perform_on_targets(perform_release, click_type, targets, &all, caches) {
+            app.record_input_error(&error);
+        }
+        release_tracked_inputs();
+    }
+}
 
+/// The targets clicks currently go to: none, meaning global clicks, while
+/// falling back from a lost target.
+fn effective_targets(state: TargetState, targets: &[ClickTarget]) -> &[ClickTarget] {
+    match state {
+        TargetState::GlobalFallback => &[],
+        _ => targets,
+    }
+}
 
-impl PyladeClickerApp {
-    fn save_current_config(&self) {
-        let hotkey_strings: Vec<String> = self.hotkey.lock().unwrap().iter()
-            .map(|k| key_to_string(k))
-            .collect();
-        
-        let click_mode_str = match *self.click_mode.lock().unwrap() {
-            ClickMode::Hold => "Hold",
-            ClickMode::Humanized => "Humanized",
-            _ => "Click",
-        };
-        
-        let click_type_str = match *self.click_type.lock().unwrap() {
-            ClickType::RightClick => "RightClick",
-            ClickType::Space => "Space",
-            _ => "LeftClick",
-        };
-        
-        let config = AppConfig {
-            hotkey: hotkey_strings,
-            click_mode: click_mode_str.to_string(),
-            click_type: click_type_str.to_string(),
-            normal_delay_ms: self.normal_delay.lock().unwrap().as_millis() as u64,
-            cps: *self.cps.lock().unwrap(),
-        };
-        
-        save_config(&config);
+/// Builds up the combination being captured from hook events. Every input
+/// pressed while capturing is added, and the combination is confirmed once all
+/// of them have been released again.
+fn capture_hotkey_input(
+    held: &[HotkeyInput],
+    input: HotkeyInput,
+    pressed: bool,
+    capturing_hotkey: &AtomicBool,
+    current_combination: &Mutex<Vec<HotkeyInput>>,
+    captured_hotkey: &Mutex<Option<Vec<HotkeyInput>>>,
+) {
+    let mut combination = current_combination.lock().unwrap();
+    
+    if pressed {
+        if !combination.contains(&input) {
+            combination.push(input);
+        }
+    } else if held.is_empty() && !combination.is_empty() {
+        *captured_hotkey.lock().unwrap() = Some(combination.clone());
+        capturing_hotkey.store(false, Ordering::SeqCst);
     }
 }
 
-impl eframe::App for PyladeClickerApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let should_refresh = {
-            let now = Instant::now();
-            let last_refresh = self.last_window_refresh.lock().unwrap();
-            now.duration_since(*last_refresh) >= Duration::from_secs(2)
+fn start_hotkey_toggle_listener(app: PyladeClickerApp) {
+    std::thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        let mut held: Vec<HotkeyInput> = Vec::new();
+        let mut detector = ChordDetector::default();
+        let mut start_detector = ChordDetector::default();
+        let mut stop_detector = ChordDetector::default();
+        let mut panic_detector = ChordDetector::default();
+        let mut pick_detector = ChordDetector::default();
+        let mut switch_detectors: Vec<ChordDetector> = Vec::new();
+        let callback = move |event: Event| {
+            if take_injected(&event.event_type) {
+                return;
+            }
+            
+            if let EventType::MouseMove { x, y } = event.event_type {
+                let zone = *app.failsafe_zone.lock().unwrap();
+                let rect = *app.failsafe_rect.lock().unwrap();
+                let active = app.clicking.load(Ordering::SeqCst) || app.is_holding.load(Ordering::SeqCst);
+                if active && in_failsafe_zone(zone, rect, x, y) {
+                    app.panic_stop();
+                    app.log_event("Failsafe triggered by the cursor; stopped clicking");
+                }
+            }
+            
+            let is_activity = !matches!(event.event_type, EventType::KeyRelease(_) | EventType::ButtonRelease(_));
+            if is_activity && app.pause_on_input.load(Ordering::SeqCst) && app.clicking_globally() {
+                let idle = *app.pause_idle.lock().unwrap();
+                *app.input_paused_until.lock().unwrap() = Some(Instant::now() + idle);
+            }
+            
+            let Some((input, pressed)) = hotkey_input_from_event(&event.event_type) else {
+                return;
+            };
+            
+            let chord_event = detector.handle(&app.hotkey.lock().unwrap(), &event.event_type, hotkey_input_is_down);
+            let start_event = start_detector.handle(&app.start_hotkey.lock().unwrap(), &event.event_type, hotkey_input_is_down);
+            let stop_event = stop_detector.handle(&app.stop_hotkey.lock().unwrap(), &event.event_type, hotkey_input_is_down);
+            let panic_event = panic_detector.handle(&app.panic_hotkey.lock().unwrap(), &event.event_type, hotkey_input_is_down);
+            let pick_event = pick_detector.handle(&app.pick_target_hotkey.lock().unwrap(), &event.event_type, hotkey_input_is_down);
+            
+            let switch_hotkeys: Vec<(String, Vec<HotkeyInput>)> = app.profiles.lock().unwrap().iter()
+                .map(|p| (p.name.clone(), parse_hotkey(&p.switch_hotkey)))
+                .collect();
+            switch_detectors.resize_with(switch_hotkeys.len(), ChordDetector::default);
+            let mut switch_to = None;
+            for ((name, chord), detector) in switch_hotkeys.iter().zip(switch_detectors.iter_mut()) {
+                if detector.handle(chord, &event.event_type, hotkey_input_is_down) == Some(ChordEvent::Pressed) {
+                    switch_to = Some(name.clone());
+                }
+            }
+            
+            if pressed {
+                held.retain(hotkey_input_is_down);
+                if !held.contains(&input) {
+                    held.push(input);
+                }
+            } else {
+                held.retain(|&k| k != input);
+            }
+            
+            if app.capturing_hotkey.load(Ordering::SeqCst) {
+                capture_hotkey_input(&held, input, pressed, &app.capturing_hotkey, &app.current_combination, &app.captured_hotkey);
+                return;
+            }
+            
+            if panic_event == Some(ChordEvent::Pressed) {
+                app.hotkeys_armed.store(false, Ordering::SeqCst);
+                app.panic_stop();
+                return;
+            }
+            
+            if !app.hotkeys_armed.load(Ordering::SeqCst) {
+                return;
+            }
+            
+            if pick_event == Some(ChordEvent::Pressed) {
+                app.pick_target_at_cursor();
+                return;
+            }
+            
+            if let Some(name) = switch_to {
+                app.switch_profile(&name);
+                return;
+            }
+            
+            if stop_event == Some(ChordEvent::Pressed) {
+                app.clicking.store(false, Ordering::SeqCst);
+            }
+            
+            if !app.clicker_armed.load(Ordering::SeqCst) {
+                return;
+            }
+            
+            if start_event == Some(ChordEvent::Pressed) && !app.clicking.load(Ordering::SeqCst) {
+                app.start_clicking();
+            }
+            
+            match chord_event {
+                Some(ChordEvent::Pressed) => {
+                    on_hotkey_pressed(&app.clicking, &app.activation_style, &app.one_shot_clicks, &app.one_shot_remaining);
+                }
+                Some(ChordEvent::Released) => {
+                    on_hotkey_released(&app.clicking, &app.activation_style);
+                }
+                None => {}
+            }
         };
         
-        if should_refresh {
-            self.refresh_windows();
-            *self.last_window_refresh.lock().unwrap() = Instant::now();
+        if let Err(error) = listen(callback) {
+            eprintln!("Failed to start hotkey toggle listener: {:?}", error);
         }
+    });
+}
+
+fn start_clicking_thread(app: PyladeClickerApp) {
+    std::thread::spawn(move || {
+        let mut rng = rand::thread_rng();
+        let mut target_caches: Vec<TargetCache> = Vec::new();
+        let mut schedules: Vec<TargetSchedule> = Vec::new();
+        let mut round_robin = 0;
+        let mut held_on: Option<(Vec<ClickTarget>, ClickType)> = None;
+        let mut one_shot_hold_until: Option<Instant> = None;
         
-        if !self.capturing_hotkey.load(Ordering::SeqCst) {
-            ctx.input(|i| {
-                let current_hotkey = self.hotkey.lock().unwrap();
-                
-                if current_hotkey.len() == 1 {
-                    if let Some(&hotkey_key) = current_hotkey.first() {
-                        let gui_key = match hotkey_key {
-                            Key::F1 => Some(egui::Key::F1),
-                            Key::F2 => Some(egui::Key::F2),
-                            Key::F3 => Some(egui::Key::F3),
-                            Key::F4 => Some(egui::Key::F4),
-                            Key::F5 => Some(egui::Key::F5),
-                            Key::F6 => Some(egui::Key::F6),
-                            Key::F7 => Some(egui::Key::F7),
-                            Key::F8 => Some(egui::Key::F8),
-                            Key::F9 => Some(egui::Key::F9),
-                            Key::F10 => Some(egui::Key::F10),
-                            Key::F11 => Some(egui::Key::F11),
-                            Key::F12 => Some(egui::Key::F12),
-                            Key::Space => Some(egui::Key::Space),
-                            Key::Return => Some(egui::Key::Enter),
-                            Key::Escape => Some(egui::Key::Escape),
-                            Key::Tab => Some(egui::Key::Tab),
-                            Key::Home => Some(egui::Key::Home),
-                            Key::End => Some(egui::Key::End),
-                            Key::PageUp => Some(egui::Key::PageUp),
-                            Key::PageDown => Some(egui::Key::PageDown),
-                            Key::Insert => Some(egui::Key::Insert),
-                            Key::Delete => Some(egui::Key::Delete),
-                            Key::UpArrow => Some(egui::Key::ArrowUp),
-                            Key::DownArrow => Some(egui::Key::ArrowDown),
-                            Key::LeftArrow => Some(egui::Key::ArrowLeft),
-                            Key::RightArrow => Some(egui::Key::ArrowRight),
-                            Key::Backspace => Some(egui::Key::Backspace),
-                            _ => None,
-                        };
-                        
-                        if let Some(gui_key) = gui_key {
-                            if i.key_pressed(gui_key) {
-                                self.clicking.store(!self.clicking.load(Ordering::SeqCst), Ordering::SeqCst);
-                            }
-                        }
-                    }
+        loop {
+            let targets = app.targets.lock().unwrap().clone();
+            let click_type = app.click_type.lock().unwrap().clone();
+            target_caches.resize_with(targets.len(), TargetCache::default);
+            
+            // Per-target holds only mean anything while something is held, and
+            // every path that releases everything clears `is_holding`
+            if !app.is_holding.load(Ordering::SeqCst) {
+                schedules.iter_mut().for_each(|schedule| schedule.holding = false);
+            }
+            
+            // A hold is released where it was pressed, so edits to the targets
+            // or click type release it before anything is pressed on the new ones
+            let current = Some((targets.clone(), click_type.clone()));
+            if held_on != current {
+                if app.is_holding.swap(false, Ordering::SeqCst) {
+                    release_tracked_inputs();
                 }
-                else if current_hotkey.len() > 1 {
-                    for &hotkey_key in current_hotkey.iter() {
-                        let gui_key = match hotkey_key {
-                            Key::F1 => Some(egui::Key::F1),
-                            Key::F2 => Some(egui::Key::F2),
-                            Key::F3 => Some(egui::Key::F3),
-                            Key::F4 => Some(egui::Key::F4),
-                            Key::F5 => Some(egui::Key::F5),
-                            Key::F6 => Some(egui::Key::F6),
-                            Key::F7 => Some(egui::Key::F7),
-                            Key::F8 => Some(egui::Key::F8),
-                            Key::F9 => Some(egui::Key::F9),
-                            Key::F10 => Some(egui::Key::F10),
-                            Key::F11 => Some(egui::Key::F11),
-                            Key::F12 => Some(egui::Key::F12),
-                            Key::Space => Some(egui::Key::Space),
-                            Key::Return => Some(egui::Key::Enter),
-                            Key::Escape => Some(egui::Key::Escape),
-                            Key::Tab => Some(egui::Key::Tab),
-                            Key::Home => Some(egui::Key::Home),
-                            Key::End => Some(egui::Key::End),
-                            Key::PageUp => Some(egui::Key::PageUp),
-                            Key::PageDown => Some(egui::Key::PageDown),
-                            Key::Insert => Some(egui::Key::Insert),
-                            Key::Delete => Some(egui::Key::Delete),
-                            Key::UpArrow => Some(egui::Key::ArrowUp),
-                            Key::DownArrow => Some(egui::Key::ArrowDown),
-                            Key::LeftArrow => Some(egui::Key::ArrowLeft),
-                            Key::RightArrow => Some(egui::Key::ArrowRight),
-                            Key::Backspace => Some(egui::Key::Backspace),
-                            _ => None,
-                        };
-                        
-                        if let Some(gui_key) = gui_key {
-                            if i.key_pressed(gui_key) {
-                                self.clicking.store(!self.clicking.load(Ordering::SeqCst), Ordering::SeqCst);
-                                break;
-                            }
-                        }
-                    }
+                held_on = current;
+            }
+            
+            if app.clicking.load(Ordering::SeqCst) {
+                let state = check_target_loss(&app, &click_type, &targets, &mut target_caches, &mut schedules);
+                if !app.clicking.load(Ordering::SeqCst) {
+                    continue;
                 }
-            });
-        }
-        
-        if self.capturing_hotkey.load(Ordering::SeqCst) {
-            ctx.input(|i| {
-                let modifiers = i.modifiers;
-                
-                let mut current_combo = Vec::new();
+                if state == TargetState::Paused {
+                    thread::sleep(Duration::from_millis(10));
+                    continue;
+                }
+                let targets = effective_targets(state, &targets);
                 
-                if modifiers.shift {
-                    current_combo.push(Key::ShiftLeft);
+                let input_paused = targets.is_empty()
+                    && app.input_paused_until.lock().unwrap().is_some_and(|until| Instant::now() < until);
+                if input_paused != app.input_paused.swap(input_paused, Ordering::SeqCst) {
+                    if input_paused {
+                        release_held(&app, &click_type, targets, &mut target_caches);
+                        app.log_event("Paused for user input");
+                    } else {
+                        app.log_event("Resumed after user input stopped");
+                    }
                 }
-                if modifiers.ctrl {
-                    current_combo.push(Key::ControlLeft);
+                if input_paused {
+                    thread::sleep(Duration::from_millis(10));
+                    continue;
                 }
-                if modifiers.alt {
-                    current_combo.push(Key::Alt);
+                
+                let condition = app.click_condition.lock().unwrap().clone();
+                let ready: Vec<bool> = targets.iter().zip(target_caches.iter_mut())
+                    .map(|(target, cache)| cache.resolve(target).is_some_and(|(hwnd, _)| condition_met(&condition, hwnd)))
+                    .collect();
+                release_unready_holds(&app, &click_type, targets, &ready, &mut schedules, &mut target_caches);
+                let waiting = !targets.is_empty() && !ready.contains(&true);
+                app.condition_waiting.store(waiting, Ordering::SeqCst);
+                if waiting {
+                    thread::sleep(Duration::from_millis(10));
+                    continue;
                 }
                 
-                for key in [
-                    (egui::Key::F1, Key::F1),
-                    (egui::Key::F2, Key::F2),
-                    (egui::Key::F3, Key::F3),
-                    (egui::Key::F4, Key::F4),
-                    (egui::Key::F5, Key::F5),
-                    (egui::Key::F6, Key::F6),
-                    (egui::Key::F7, Key::F7),
-                    (egui::Key::F8, Key::F8),
-                    (egui::Key::F9, Key::F9),
-                    (egui::Key::F10, Key::F10),
-                    (egui::Key::F11, Key::F11),
-                    (egui::Key::F12, Key::F12),
-                    (egui::Key::Home, Key::Home),
-                    (egui::Key::End, Key::End),
-                    (egui::Key::PageUp, Key::PageUp),
-                    (egui::Key::PageDown, Key::PageDown),
-                    (egui::Key::Insert, Key::Insert),
-                    (egui::Key::Delete, Key::Delete),
-                    (egui::Key::ArrowUp, Key::UpArrow),
-                    (egui::Key::ArrowDown, Key::DownArrow),
-                    (egui::Key::ArrowLeft, Key::LeftArrow),
-                    (egui::Key::ArrowRight, Key::RightArrow),
-                    (egui::Key::Space, Key::Space),
-                    (egui::Key::Enter, Key::Return),
-                    (egui::Key::Escape, Key::Escape),
-                    (egui::Key::Tab, Key::Tab),
-                    (egui::Key::Backspace, Key::Backspace),
-                ] {
-                    if i.key_down(key.0) && !current_combo.contains(&key.1) {
-                        current_combo.push(key.1);
+                let mode = app.click_mode.lock().unwrap().clone();
+                let strategy = app.target_strategy.lock().unwrap().clone();
+                
+                // A one-shot hold counts its clicks as click delays spent holding
+                let one_shot_left = *app.one_shot_remaining.lock().unwrap();
+                if let (true, Some(left)) = (app.is_holding.load(Ordering::SeqCst), one_shot_left) {
+                    let delay = *app.normal_delay.lock().unwrap();
+                    let until = *one_shot_hold_until.get_or_insert_with(|| Instant::now() + delay * left);
+                    if Instant::now() >= until {
+                        record_one_shot_clicks(&app.one_shot_remaining, &app.clicking, left);
+                        continue;
                     }
                 }
                 
-                *self.current_combination.lock().unwrap() = current_combo.clone();
-                
-                if current_combo.len() >= 2 {
-                    *self.hotkey.lock().unwrap() = current_combo;
-                    *self.listening_text.lock().unwrap() = String::new();
-                    self.capturing_hotkey.store(false, Ordering::SeqCst);
-                    *self.current_combination.lock().unwrap() = Vec::new();
-                    self.save_current_config();
+                if strategy == TargetStrategy::Independent && !targets.is_empty() {
+                    schedules.resize_with(targets.len(), TargetSchedule::default);
+                    let wait = run_target_schedules(&app, &click_type, targets, &ready, &mut schedules, &mut target_caches, &mut rng);
+                    thread::sleep(wait);
+                    continue;
                 }
                 
-                for event in &i.events {
-                    if let egui::Event::Key { key, pressed: false, .. } = event {
-                        let rdev_key = match key {
-                            egui::Key::F1 => Some(Key::F1),
-                            egui::Key::F2 => Some(Key::F2),
-                            egui::Key::F3 => Some(Key::F3),
-                            egui::Key::F4 => Some(Key::F4),
-                            egui::Key::F5 => Some(Key::F5),
-                            egui::Key::F6 => Some(Key::F6),
-                            egui::Key::F7 => Some(Key::F7),
-                            egui::Key::F8 => Some(Key::F8),
-                            egui::Key::F9 => Some(Key::F9),
-                            egui::Key::F10 => Some(Key::F10),
-                            egui::Key::F11 => Some(Key::F11),
-                            egui::Key::F12 => Some(Key::F12),
-                            egui::Key::Space => Some(Key::Space),
-                            egui::Key::Enter => Some(Key::Return),
-                            egui::Key::Escape => Some(Key::Escape),
-                            egui::Key::Tab => Some(Key::Tab),
-                            egui::Key::Home => Some(Key::Home),
-                            egui::Key::End => Some(Key::End),
-                            egui::Key::PageUp => Some(Key::PageUp),
-                            egui::Key::PageDown => Some(Key::PageDown),
-                            egui::Key::Insert => Some(Key::Insert),
-                            egui::Key::Delete => Some(Key::Delete),
-                            egui::Key::ArrowUp => Some(Key::UpArrow),
-                            egui::Key::ArrowDown => Some(Key::DownArrow),
-                            egui::Key::ArrowLeft => Some(Key::LeftArrow),
-                            egui::Key::ArrowRight => Some(Key::RightArrow),
-                            egui::Key::Backspace => Some(Key::Backspace),
-                            _ => None,
-                        };
-                        
-                        if let Some(rdev_key) = rdev_key {
-                            *self.hotkey.lock().unwrap() = vec![rdev_key];
-                            *self.listening_text.lock().unwrap() = String::new();
-                            self.capturing_hotkey.store(false, Ordering::SeqCst);
-                            *self.current_combination.lock().unwrap() = Vec::new();
-                            self.save_current_config();
-                            break;
+                let mut click = || {
+                    let selected = next_targets(&strategy, &ready, &mut round_robin);
+                    app.report_input(perform_on_targets(perform_click, &click_type, targets, &selected, &mut target_caches))
+                };
+                
+                match mode {
+                    ClickMode::Click => {
+                        let delay = *app.normal_delay.lock().unwrap();
+                        if click() {
+                            record_one_shot_clicks(&app.one_shot_remaining, &app.clicking, 1);
                         }
+                        thread::sleep(delay);
                     }
+                    ClickMode::Hold if targets.is_empty() => {
+                        if !app.is_holding.load(Ordering::SeqCst) {
+                            let held = perform_on_targets(perform_hold, &click_type, targets, &[], &mut target_caches);
+                            if app.report_input(held) {
+                                app.is_holding.store(true, Ordering::SeqCst);
+                            }
+                        }
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    ClickMode::Hold => {
+                        schedules.resize_with(targets.len(), TargetSchedule::default);
+                        for (index, target) in targets.iter().enumerate() {
+                            if ready[index] && !schedules[index].holding
+                                && app.report_input(perform_hold(&click_type, Some(target), &mut target_caches[index]))
+                            {
+                                schedules[index].holding = true;
+                                app.is_holding.store(true, Ordering::SeqCst);
+                            }
+                        }
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    ClickMode::Humanized => {
+                        let cps_value = *app.cps.lock().unwrap();
+                        
+                        if cps_value > 50.0 {
+                            let limit = *app.one_shot_remaining.lock().unwrap();
+                            let performed = drag_click_burst(cps_value, &mut rng, limit, click);
+                            record_one_shot_clicks(&app.one_shot_remaining, &app.clicking, performed);
+                            
+                            let break_time = Duration::from_millis(rng.gen_range(450..=550));
+                            thread::sleep(break_time);
+                        } else {
+                            if click() {
+                                record_one_shot_clicks(&app.one_shot_remaining, &app.clicking, 1);
+                            }
+                            
+                            let delay = calculate_humanized_delay(cps_value, &mut rng);
+                            thread::sleep(delay);
+                        }
+                    }
+                }
+            } else {
+                schedules.clear();
+                one_shot_hold_until = None;
+                app.condition_waiting.store(false, Ordering::SeqCst);
+                app.input_paused.store(false, Ordering::SeqCst);
+                *app.input_paused_until.lock().unwrap() = None;
+                let state = std::mem::replace(&mut *app.target_state.lock().unwrap(), TargetState::Found);
+                release_held(&app, &click_type, effective_targets(state, &targets), &mut target_caches);
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    });
+}
+
+/// Checks whether any target can still be found and applies the profile's
+/// target loss action when none can. Whatever is held is released when
+/// clicking moves between the targets and the global fallback, so nothing
+/// stays pressed where clicks no longer go.
+fn check_target_loss(
+    app: &PyladeClickerApp,
+    click_type: &ClickType,
+    targets: &[ClickTarget],
+    caches: &mut [TargetCache],
+    schedules: &mut Vec<TargetSchedule>,
+) -> TargetState {
+    let lost = !targets.is_empty()
+        && targets.iter().zip(caches.iter_mut()).all(|(target, cache)| cache.resolve(target).is_none());
+    let previous = *app.target_state.lock().unwrap();
+    
+    let state = if !lost {
+        TargetState::Found
+    } else {
+        match *app.target_loss_action.lock().unwrap() {
+            TargetLossAction::Stop => {
+                app.clicking.store(false, Ordering::SeqCst);
+                app.log_event("Target lost; stopped clicking");
+                return previous;
+            }
+            TargetLossAction::Pause => TargetState::Paused,
+            TargetLossAction::Global => TargetState::GlobalFallback,
+        }
+    };
+    
+    if state != previous {
+        release_held(app, click_type, effective_targets(previous, targets), caches);
+        schedules.clear();
+        *app.target_state.lock().unwrap() = state;
+        app.log_event(match state {
+            TargetState::Found => "Target found again; clicking it",
+            TargetState::Paused => "Target lost; paused until it reappears",
+            TargetState::GlobalFallback => "Target lost; clicking globally until it reappears",
+        });
+    }
+    
+    state
+}
+
+/// Picks which of the `ready` targets the next click goes to: the next one in
+/// turn for round-robin, otherwise all of them.
+fn next_targets(strategy: &TargetStrategy, ready: &[bool], round_robin: &mut usize) -> Vec<usize> {
+    let count = ready.len();
+    match strategy {
+        TargetStrategy::RoundRobin => {
+            let next = (0..count).map(|offset| (*round_robin + offset) % count).find(|&index| ready[index]);
+            match next {
+                Some(index) => {
+                    *round_robin = index + 1;
+                    vec![index]
+                }
+                None => Vec::new(),
+            }
+        }
+        _ => (0..count).filter(|&index| ready[index]).collect(),
+    }
+}
+
+/// Whether clicks may be sent to `hwnd` under the profile's click condition.
+fn condition_met(condition: &ClickCondition, hwnd: HWND) -> bool {
+    unsafe {
+        match condition {
+            ClickCondition::Always => true,
+            ClickCondition::Foreground => GetForegroundWindow() == GetAncestor(hwnd, GA_ROOT),
+            ClickCondition::Background => GetForegroundWindow() != GetAncestor(hwnd, GA_ROOT),
+            ClickCondition::CursorInside => {
+                let mut cursor = POINT::default();
+                let mut rect = RECT::default();
+                GetCursorPos(&mut cursor).as_bool()
+                    && GetWindowRect(hwnd, &mut rect).as_bool()
+                    && (rect.left..rect.right).contains(&cursor.x)
+                    && (rect.top..rect.bottom).contains(&cursor.y)
+            }
+        }
+    }
+}
+
+/// Runs `action` against the selected targets, or once globally when there
+/// are no targets at all. A failure on one target doesn't skip the rest; the
+/// last error is returned.
+fn perform_on_targets(
+    action: fn(&ClickType, Option<&ClickTarget>, &mut TargetCache) -> Result<(), rdev::SimulateError>,
+    click_type: &ClickType,
+    targets: &[ClickTarget],
+    selected: &[usize],
+    caches: &mut [TargetCache],
+) -> Result<(), rdev::SimulateError> {
+    if targets.is_empty() {
+        return action(click_type, None, &mut TargetCache::default());
+    }
+    
+    let mut result = Ok(());
+    for &index in selected {
+        if let Err(error) = action(click_type, Some(&targets[index]), &mut caches[index]) {
+            result = Err(error);
+        }
+    }
+    result
+}
+
+/// Releases the hold on every target that has stopped meeting the click
+/// condition, so no button stays down in a window that should be idle.
+fn release_unready_holds(
+    app: &PyladeClickerApp,
+    click_type: &ClickType,
+    targets: &[ClickTarget],
+    ready: &[bool],
+    schedules: &mut [TargetSchedule],
+    caches: &mut [TargetCache],
+) {
+    for (index, target) in targets.iter().enumerate().take(schedules.len()) {
+        if schedules[index].holding && !ready[index] {
+            if let Err(error) = perform_release(click_type, Some(target), &mut caches[index]) {
+                app.record_input_error(&error);
+            }
+            schedules[index].holding = false;
+        }
+    }
+    
+    if !targets.is_empty() && !schedules.iter().any(|schedule| schedule.holding) {
+        app.is_holding.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Clicks every `ready` target that is due under its own mode and delay. A
+/// target that isn't ready keeps its place in the schedule and clicks as soon
+/// as it is. Returns how long the clicking thread can sleep before the next
+/// target is due.
+fn run_target_schedules(
+    app: &PyladeClickerApp,
+    click_type: &ClickType,
+    targets: &[ClickTarget],
+    ready: &[bool],
+    schedules: &mut [TargetSchedule],
+    caches: &mut [TargetCache],
+    rng: &mut impl rand::Rng,
+) -> Duration {
+    let now = Instant::now();
+    let mut wait = Duration::from_millis(10);
+    
+    for (index, target) in targets.iter().enumerate() {
+        if !app.clicking.load(Ordering::SeqCst) {
+            break;
+        }
+        
+        if !ready[index] {
+            continue;
+        }
+        
+        let schedule = &mut schedules[index];
+        let cache = &mut caches[index];
+        
+        if target.click_mode == "Hold" {
+            if !schedule.holding && app.report_input(perform_hold(click_type, Some(target), cache)) {
+                schedule.holding = true;
+                app.is_holding.store(true, Ordering::SeqCst);
+            }
+            continue;
+        }
+        
+        if schedule.holding {
+            if let Err(error) = perform_release(click_type, Some(target), cache) {
+                app.record_input_error(&error);
+            }
+            schedule.holding = false;
+        }
+        
+        let due = schedule.next_due.unwrap_or(now);
+        if due <= now {
+            if app.report_input(perform_click(click_type, Some(target), cache)) {
+                record_one_shot_clicks(&app.one_shot_remaining, &app.clicking, 1);
+            }
+            
+            let delay = if target.click_mode == "Humanized" {
+                calculate_humanized_delay(target.cps, rng)
+            } else {
+                Duration::from_millis(target.normal_delay_ms)
+            };
+            schedule.next_due = Some(now + delay);
+        }
+        
+        if let Some(next_due) = schedule.next_due {
+            wait = wait.min(next_due.saturating_duration_since(now));
+        }
+    }
+    
+    wait
+}
+
+fn calculate_humanized_delay(cps: f32, rng: &mut impl rand::Rng) -> Duration {
+    let base_delay_ms = 1000.0 / cps;
+    let variation = if cps > 20.0 {
+        rng.gen_range(-3.0..=3.0)
+    } else if cps > 10.0 {
+        rng.gen_range(-5.0..=5.0)
+    } else {
+        rng.gen_range(-10.0..=10.0)
+    };
+    let final_delay = (base_delay_ms + variation).max(1.0);
+    
+    
+    Duration::from_millis(final_delay as u64)
+}
+
+fn drag_click_burst(
+    target_cps: f32,
+    rng: &mut impl rand::Rng,
+    limit: Option<u32>,
+    mut click: impl FnMut() -> bool,
+) -> u32 {
+    let base_burst_size = (target_cps * 0.5) as u32;
+    let mut burst_count = rng.gen_range((base_burst_size.saturating_sub(5))..=(base_burst_size + 5));
+    if let Some(limit) = limit {
+        burst_count = burst_count.min(limit);
+    }
+    
+    let burst_delay = Duration::from_micros(rng.gen_range(500..=1500));
+    
+    for i in 0..burst_count {
+        if !click() {
+            return i;
+        }
+        
+        if i + 1 < burst_count {
+            thread::sleep(burst_delay);
+        }
+    }
+    
+    burst_count
+}
+
+fn perform_click(click_type: &ClickType, target: Option<&ClickTarget>, cache: &mut TargetCache) -> Result<(), rdev::SimulateError> {
+    match click_type {
+        ClickType::LeftClick => {
+            if let Some(target) = target {
+                if let Some((hwnd, point)) = cache.resolve(target) {
+                    click_target_window(hwnd, point);
+                }
+            } else {
+                inject(&EventType::ButtonPress(Button::Left))?;
+                thread::sleep(Duration::from_millis(1));
+                inject(&EventType::ButtonRelease(Button::Left))?;
+            }
+        }
+        ClickType::RightClick => {
+            if let Some(target) = target {
+                if let Some((hwnd, point)) = cache.resolve(target) {
+                    right_click_target_window(hwnd, point);
+                }
+            } else {
+                inject(&EventType::ButtonPress(Button::Right))?;
+                thread::sleep(Duration::from_millis(1));
+                inject(&EventType::ButtonRelease(Button::Right))?;
+            }
+        }
+        ClickType::Space => {
+            if let Some(target) = target {
+                if let Some((hwnd, _)) = cache.resolve(target) {
+                    space_target_window(hwnd);
+                }
+            } else {
+                inject(&EventType::KeyPress(Key::Space))?;
+                thread::sleep(Duration::from_millis(1));
+                inject(&EventType::KeyRelease(Key::Space))?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+fn perform_hold(click_type: &ClickType, target: Option<&ClickTarget>, cache: &mut TargetCache) -> Result<(), rdev::SimulateError> {
+    match click_type {
+        ClickType::LeftClick => {
+            if let Some(target) = target {
+                if let Some((hwnd, point)) = cache.resolve(target) {
+                    hold_target_window(hwnd, point);
+                }
+            } else {
+                inject(&EventType::ButtonPress(Button::Left))?;
+                track_press(HeldInput::Global(EventType::ButtonRelease(Button::Left)));
+            }
+        }
+        ClickType::RightClick => {
+            if let Some(target) = target {
+                if let Some((hwnd, point)) = cache.resolve(target) {
+                    right_hold_target_window(hwnd, point);
+                }
+            } else {
+                inject(&EventType::ButtonPress(Button::Right))?;
+                track_press(HeldInput::Global(EventType::ButtonRelease(Button::Right)));
+            }
+        }
+        ClickType::Space => {
+            if let Some(target) = target {
+                if let Some((hwnd, _)) = cache.resolve(target) {
+                    space_hold_target_window(hwnd);
+                }
+            } else {
+                inject(&EventType::KeyPress(Key::Space))?;
+                track_press(HeldInput::Global(EventType::KeyRelease(Key::Space)));
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+fn perform_release(click_type: &ClickType, target: Option<&ClickTarget>, cache: &mut TargetCache) -> Result<(), rdev::SimulateError> {
+    match click_type {
+        ClickType::LeftClick => {
+            if let Some(target) = target {
+                if let Some((hwnd, point)) = cache.resolve(target) {
+                    release_target_window(hwnd, point);
+                }
+            } else {
+                inject(&EventType::ButtonRelease(Button::Left))?;
+                track_release(HeldInput::Global(EventType::ButtonRelease(Button::Left)));
+            }
+        }
+        ClickType::RightClick => {
+            if let Some(target) = target {
+                if let Some((hwnd, point)) = cache.resolve(target) {
+                    right_release_target_window(hwnd, point);
+                }
+            } else {
+                inject(&EventType::ButtonRelease(Button::Right))?;
+                track_release(HeldInput::Global(EventType::ButtonRelease(Button::Right)));
+            }
+        }
+        ClickType::Space => {
+            if let Some(target) = target {
+                if let Some((hwnd, _)) = cache.resolve(target) {
+                    space_release_target_window(hwnd);
+                }
+            } else {
+                inject(&EventType::KeyRelease(Key::Space))?;
+                track_release(HeldInput::Global(EventType::KeyRelease(Key::Space)));
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Packs the client coordinates a mouse message is sent at: `point` if the
+/// target has one, otherwise the centre of the window's client area.
+fn client_lparam(hwnd: HWND, point: Option<POINT>) -> LPARAM {
+    let point = point.unwrap_or_else(|| {
+        let mut client_rect = RECT::default();
+        unsafe { GetClientRect(hwnd, &mut client_rect) };
+        POINT {
+            x: (client_rect.left + client_rect.right) / 2,
+            y: (client_rect.top + client_rect.bottom) / 2,
+        }
+    });
+    
+    LPARAM((((point.y as u32) << 16) | (point.x as u16 as u32)) as isize)
+}
+
+fn click_target_window(hwnd: HWND, point: Option<POINT>) {
+    unsafe {
+        let lparam = client_lparam(hwnd, point);
+        
+        PostMessageW(hwnd, WM_LBUTTONDOWN, WPARAM(1), lparam);
+        thread::sleep(Duration::from_millis(1));
+        PostMessageW(hwnd, WM_LBUTTONUP, WPARAM(0), lparam);
+    }
+}
+
+fn right_click_target_window(hwnd: HWND, point: Option<POINT>) {
+    unsafe {
+        let lparam = client_lparam(hwnd, point);
+        
+        PostMessageW(hwnd, WM_RBUTTONDOWN, WPARAM(1), lparam);
+        thread::sleep(Duration::from_millis(1));
+        PostMessageW(hwnd, WM_RBUTTONUP, WPARAM(0), lparam);
+    }
+}
+
+fn space_target_window(hwnd: HWND) {
+    unsafe {
+        PostMessageW(hwnd, WM_KEYDOWN, WPARAM(key_to_vk(&Key::Space).unwrap_or(0x20) as usize), LPARAM(0));
+        thread::sleep(Duration::from_millis(1));
+        PostMessageW(hwnd, WM_KEYUP, WPARAM(key_to_vk(&Key::Space).unwrap_or(0x20) as usize), LPARAM(0));
+    }
+}
+
+fn hold_target_window(hwnd: HWND, point: Option<POINT>) {
+    unsafe {
+        let lparam = client_lparam(hwnd, point);
+        
+        PostMessageW(hwnd, WM_LBUTTONDOWN, WPARAM(1), lparam);
+        track_press(HeldInput::Window { hwnd: hwnd.0, message: WM_LBUTTONUP, wparam: 0, lparam: lparam.0 });
+    }
+}
+
+fn release_target_window(hwnd: HWND, point: Option<POINT>) {
+    unsafe {
+        let lparam = client_lparam(hwnd, point);
+        
+        PostMessageW(hwnd, WM_LBUTTONUP, WPARAM(0), lparam);
+        track_release(HeldInput::Window { hwnd: hwnd.0, message: WM_LBUTTONUP, wparam: 0, lparam: lparam.0 });
+    }
+}
+
+fn right_hold_target_window(hwnd: HWND, point: Option<POINT>) {
+    unsafe {
+        let lparam = client_lparam(hwnd, point);
+        
+        PostMessageW(hwnd, WM_RBUTTONDOWN, WPARAM(1), lparam);
+        track_press(HeldInput::Window { hwnd: hwnd.0, message: WM_RBUTTONUP, wparam: 0, lparam: lparam.0 });
+    }
+}
+
+fn right_release_target_window(hwnd: HWND, point: Option<POINT>) {
+    unsafe {
+        let lparam = client_lparam(hwnd, point);
+        
+        PostMessageW(hwnd, WM_RBUTTONUP, WPARAM(0), lparam);
+        track_release(HeldInput::Window { hwnd: hwnd.0, message: WM_RBUTTONUP, wparam: 0, lparam: lparam.0 });
+    }
+}
+
+fn space_hold_target_window(hwnd: HWND) {
+    let vk = key_to_vk(&Key::Space).unwrap_or(0x20) as usize;
+    unsafe {
+        PostMessageW(hwnd, WM_KEYDOWN, WPARAM(vk), LPARAM(0));
+    }
+    track_press(HeldInput::Window { hwnd: hwnd.0, message: WM_KEYUP, wparam: vk, lparam: 0 });
+}
+
+fn space_release_target_window(hwnd: HWND) {
+    let vk = key_to_vk(&Key::Space).unwrap_or(0x20) as usize;
+    unsafe {
+        PostMessageW(hwnd, WM_KEYUP, WPARAM(vk), LPARAM(0));
+    }
+    track_release(HeldInput::Window { hwnd: hwnd.0, message: WM_KEYUP, wparam: vk, lparam: 0 });
+}
+
+
+
+
+
+
+
+
+
+
+
+impl PyladeClickerApp {
+    /// Records the live settings in the profile list and schedules a write. The
+    /// config saver thread writes the file once no change has been requested
+    /// for `CONFIG_SAVE_DEBOUNCE`.
+    fn save_current_config(&self) {
+        self.store_active_profile();
+        *self.save_requested.lock().unwrap() = Some(Instant::now());
+    }
+    
+    fn flush_config(&self) {
+        self.store_active_profile();
+        
+        let config = AppConfig {
+            version: CONFIG_VERSION,
+            active_profile: self.active_profile.lock().unwrap().clone(),
+            profiles: self.profiles.lock().unwrap().clone(),
+            start_hotkey: hotkey_to_strings(&self.start_hotkey.lock().unwrap()),
+            stop_hotkey: hotkey_to_strings(&self.stop_hotkey.lock().unwrap()),
+            panic_hotkey: hotkey_to_strings(&self.panic_hotkey.lock().unwrap()),
+            pick_target_hotkey: hotkey_to_strings(&self.pick_target_hotkey.lock().unwrap()),
+            auto_rules: self.auto_rules.lock().unwrap().clone(),
+            failsafe: failsafe_zone_to_str(*self.failsafe_zone.lock().unwrap()).to_string(),
+            failsafe_rect: *self.failsafe_rect.lock().unwrap(),
+        };
+        
+        let mut config_mtime = self.config_mtime.lock().unwrap();
+        let result = save_config(&config);
+        if result.is_ok() {
+            *config_mtime = config_file_mtime();
+        }
+        
+        *self.config_save_error.lock().unwrap() = result
+            .err()
+            .map(|e| format!("Failed to save config: {}", e));
+    }
+    
+    /// Replaces the profiles, rules and global hotkeys with `config` and loads
+    /// its active profile.
+    fn apply_config(&self, config: &AppConfig) {
+        if self.is_holding.load(Ordering::SeqCst) {
+            self.panic_stop();
+        }
+        
+        let active_profile = config.profiles.iter()
+            .find(|p| p.name == config.active_profile)
+            .or(config.profiles.first())
+            .cloned()
+            .unwrap_or_default();
+        
+        *self.profiles.lock().unwrap() = config.profiles.clone();
+        *self.auto_rules.lock().unwrap() = config.auto_rules.clone();
+        *self.start_hotkey.lock().unwrap() = parse_hotkey(&config.start_hotkey);
+        *self.stop_hotkey.lock().unwrap() = parse_hotkey(&config.stop_hotkey);
+        *self.panic_hotkey.lock().unwrap() = parse_hotkey(&config.panic_hotkey);
+        *self.pick_target_hotkey.lock().unwrap() = parse_hotkey(&config.pick_target_hotkey);
+        *self.failsafe_zone.lock().unwrap() = failsafe_zone_from_str(&config.failsafe);
+        *self.failsafe_rect.lock().unwrap() = config.failsafe_rect;
+        self.apply_profile(&active_profile);
+    }
+    
+    /// Picks up a config file changed by something other than this app. If
+    /// the GUI has edits that haven't been written yet, the change is held
+    /// back until the user chooses which side to keep.
+    fn reload_config_from_disk(&self) {
+        let config_path = get_config_path();
+        let Ok(config_data) = fs::read_to_string(&config_path) else {
+            return;
+        };
+        
+        let mut config = match parse_config(&config_data) {
+            Ok(config) => config,
+            Err(error) => {
+                // The next save would overwrite the unreadable file, so keep a copy
+                let backup_note = match backup_config_file(&config_path) {
+                    Some(backup_path) => format!("The changed file was backed up to {}.", backup_path.display()),
+                    None => "The changed file could not be backed up.".to_string(),
+                };
+                *self.external_config_error.lock().unwrap() = Some(format!("{}\n{}", error, backup_note));
+                return;
+            }
+        };
+        
+        let errors = validate_config(&mut config);
+        let mut corrected = false;
+        if !errors.is_empty() {
+            let (report, backed_up) = report_config_errors(&errors, &config_path);
+            *self.config_error.lock().unwrap() = Some(report);
+            corrected = backed_up;
+        }
+        
+        if self.save_requested.lock().unwrap().is_some() {
+            *self.external_config.lock().unwrap() = Some(config);
+        } else {
+            self.apply_config(&config);
+            if corrected {
+                self.save_current_config();
+            }
+        }
+    }
+    
+    fn current_profile(&self) -> ProfileConfig {
+        let click_mode_str = match *self.click_mode.lock().unwrap() {
+            ClickMode::Hold => "Hold",
+            ClickMode::Humanized => "Humanized",
+            _ => "Click",
+        };
+        
+        let click_type_str = match *self.click_type.lock().unwrap() {
+            ClickType::RightClick => "RightClick",
+            ClickType::Space => "Space",
+            _ => "LeftClick",
+        };
+        
+        let activation_style_str = match *self.activation_style.lock().unwrap() {
+            ActivationStyle::HoldToRun => "HoldToRun",
+            ActivationStyle::OneShot => "OneShot",
+            _ => "Toggle",
+        };
+        
+        let target_strategy_str = match *self.target_strategy.lock().unwrap() {
+            TargetStrategy::AllAtOnce => "AllAtOnce",
+            TargetStrategy::Independent => "Independent",
+            _ => "RoundRobin",
+        };
+        
+        let target_loss_action_str = match *self.target_loss_action.lock().unwrap() {
+            TargetLossAction::Stop => "Stop",
+            TargetLossAction::Global => "Global",
+            _ => "Pause",
+        };
+        
+        let click_condition_str = match *self.click_condition.lock().unwrap() {
+            ClickCondition::Foreground => "Foreground",
+            ClickCondition::Background => "Background",
+            ClickCondition::CursorInside => "CursorInside",
+            _ => "Always",
+        };
+        
+        ProfileConfig {
+            name: self.active_profile.lock().unwrap().clone(),
+            hotkey: hotkey_to_strings(&self.hotkey.lock().unwrap()),
+            switch_hotkey: hotkey_to_strings(&self.profile_switch_hotkey.lock().unwrap()),
+            click_mode: click_mode_str.to_string(),
+            click_type: click_type_str.to_string(),
+            normal_delay_ms: self.normal_delay.lock().unwrap().as_millis() as u64,
+            cps: *self.cps.lock().unwrap(),
+            activation_style: activation_style_str.to_string(),
+            one_shot_clicks: *self.one_shot_clicks.lock().unwrap(),
+            targets: self.targets.lock().unwrap().clone(),
+            target_strategy: target_strategy_str.to_string(),
+            target_loss_action: target_loss_action_str.to_string(),
+            click_condition: click_condition_str.to_string(),
+            pause_on_input: self.pause_on_input.load(Ordering::SeqCst),
+            pause_idle_ms: self.pause_idle.lock().unwrap().as_millis() as u64,
+        }
+    }
+    
+    /// Writes the live settings back into the active entry of the profile list.
+    fn store_active_profile(&self) {
+        let current = self.current_profile();
+        let mut profiles = self.profiles.lock().unwrap();
+        if let Some(profile) = profiles.iter_mut().find(|p| p.name == current.name) {
+            *profile = current;
+        }
+    }
+    
+    fn apply_profile(&self, profile: &ProfileConfig) {
+        let click_mode = match profile.click_mode.as_str() {
+            "Hold" => ClickMode::Hold,
+            "Humanized" => ClickMode::Humanized,
+            _ => ClickMode::Click,
+        };
+        
+        let click_type = match profile.click_type.as_str() {
+            "RightClick" => ClickType::RightClick,
+            "Space" => ClickType::Space,
+            _ => ClickType::LeftClick,
+        };
+        
+        let activation_style = match profile.activation_style.as_str() {
+            "HoldToRun" => ActivationStyle::HoldToRun,
+            "OneShot" => ActivationStyle::OneShot,
+            _ => ActivationStyle::Toggle,
+        };
+        
+        let target_strategy = match profile.target_strategy.as_str() {
+            "AllAtOnce" => TargetStrategy::AllAtOnce,
+            "Independent" => TargetStrategy::Independent,
+            _ => TargetStrategy::RoundRobin,
+        };
+        
+        let target_loss_action = match profile.target_loss_action.as_str() {
+            "Stop" => TargetLossAction::Stop,
+            "Global" => TargetLossAction::Global,
+            _ => TargetLossAction::Pause,
+        };
+        
+        let click_condition = match profile.click_condition.as_str() {
+            "Foreground" => ClickCondition::Foreground,
+            "Background" => ClickCondition::Background,
+            "CursorInside" => ClickCondition::CursorInside,
+            _ => ClickCondition::Always,
+        };
+        
+        let hotkey = parse_hotkey(&profile.hotkey);
+        
+        *self.click_mode.lock().unwrap() = click_mode;
+        *self.click_type.lock().unwrap() = click_type;
+        *self.activation_style.lock().unwrap() = activation_style;
+        *self.normal_delay.lock().unwrap() = Duration::from_millis(profile.normal_delay_ms);
+        *self.cps.lock().unwrap() = profile.cps;
+        *self.one_shot_clicks.lock().unwrap() = profile.one_shot_clicks.max(1);
+        *self.targets.lock().unwrap() = profile.targets.clone();
+        *self.target_strategy.lock().unwrap() = target_strategy;
+        *self.target_loss_action.lock().unwrap() = target_loss_action;
+        *self.click_condition.lock().unwrap() = click_condition;
+        self.pause_on_input.store(profile.pause_on_input, Ordering::SeqCst);
+        *self.pause_idle.lock().unwrap() = Duration::from_millis(profile.pause_idle_ms);
+        *self.hotkey.lock().unwrap() = if hotkey.is_empty() { vec![HotkeyInput::Key(Key::F6)] } else { hotkey };
+        *self.profile_switch_hotkey.lock().unwrap() = parse_hotkey(&profile.switch_hotkey);
+        *self.active_profile.lock().unwrap() = profile.name.clone();
+    }
+    
+    /// Stops clicking, releasing anything held with the outgoing profile's
+    /// settings, and loads the named profile.
+    fn switch_profile(&self, name: &str) {
+        if *self.active_profile.lock().unwrap() == name {
+            return;
+        }
+        
+        self.store_active_profile();
+        let profile = self.profiles.lock().unwrap().iter().find(|p| p.name == name).cloned();
+        
+        if let Some(profile) = profile {
+            self.panic_stop();
+            self.apply_profile(&profile);
+            self.save_current_config();
+        }
+    }
+    
+    fn unique_profile_name(&self, base: &str) -> String {
+        let profiles = self.profiles.lock().unwrap();
+        let mut name = base.to_string();
+        let mut n = 2;
+        while profiles.iter().any(|p| p.name == name) {
+            name = format!("{} {}", base, n);
+            n += 1;
+        }
+        name
+    }
+    
+    fn duplicate_profile(&mut self) {
+        self.store_active_profile();
+        let mut profile = self.current_profile();
+        profile.name = self.unique_profile_name(&format!("{} copy", profile.name));
+        profile.switch_hotkey.clear();
+        
+        let name = profile.name.clone();
+        self.profiles.lock().unwrap().push(profile);
+        self.switch_profile(&name);
+        self.profile_name_edit = name;
+    }
+    
+    fn rename_profile(&mut self) {
+        let new_name = self.profile_name_edit.trim().to_string();
+        let old_name = self.active_profile.lock().unwrap().clone();
+        if new_name.is_empty() || new_name == old_name {
+            return;
+        }
+        if self.profiles.lock().unwrap().iter().any(|p| p.name == new_name) {
+            self.profile_name_edit = old_name;
+            return;
+        }
+        
+        self.store_active_profile();
+        if let Some(profile) = self.profiles.lock().unwrap().iter_mut().find(|p| p.name == old_name) {
+            profile.name = new_name.clone();
+        }
+        for rule in self.auto_rules.lock().unwrap().iter_mut() {
+            if rule.profile == old_name {
+                rule.profile = new_name.clone();
+            }
+        }
+        *self.active_profile.lock().unwrap() = new_name;
+        self.save_current_config();
+    }
+    
+    fn delete_profile(&mut self) {
+        let name = self.active_profile.lock().unwrap().clone();
+        let next = {
+            let profiles = self.profiles.lock().unwrap();
+            if profiles.len() <= 1 {
+                return;
+            }
+            profiles.iter().find(|p| p.name != name).map(|p| p.name.clone())
+        };
+        
+        if let Some(next) = next {
+            self.switch_profile(&next);
+            self.profiles.lock().unwrap().retain(|p| p.name != name);
+            self.auto_rules.lock().unwrap().retain(|rule| rule.profile != name);
+            self.profile_name_edit = next;
+            self.save_current_config();
+        }
+    }
+    
+    fn start_clicking(&self) {
+        start_clicking(&self.clicking, &self.activation_style, &self.one_shot_clicks, &self.one_shot_remaining);
+    }
+    
+    /// Stops clicking at once and releases anything held, wherever it was
+    /// pressed.
+    fn panic_stop(&self) {
+        self.clicking.store(false, Ordering::SeqCst);
+        *self.one_shot_remaining.lock().unwrap() = None;
+        
+        // This runs inside the input hook, which Windows drops if it is slow,
+        // so the tracker releases holds rather than a search for the targets
+        if self.is_holding.swap(false, Ordering::SeqCst) {
+            release_tracked_inputs();
+        }
+    }
+    
+    /// Whether clicks currently go to the cursor rather than to target windows.
+    fn clicking_globally(&self) -> bool {
+        self.clicking.load(Ordering::SeqCst)
+            && (self.targets.lock().unwrap().is_empty()
+                || *self.target_state.lock().unwrap() == TargetState::GlobalFallback)
+    }
+    
+    fn log_event(&self, message: &str) {
+        let elapsed = self.session_start.elapsed().as_secs();
+        let mut session_log = self.session_log.lock().unwrap();
+        session_log.push(format!(
+            "[{:02}:{:02}:{:02}] {}",
+            elapsed / 3600, elapsed / 60 % 60, elapsed % 60, message
+        ));
+        if session_log.len() > SESSION_LOG_LEN {
+            session_log.remove(0);
+        }
+    }
+    
+    /// Shows a failed input in the GUI and the session log.
+    fn record_input_error(&self, error: &rdev::SimulateError) {
+        let message = format!("Input failed: {}", error);
+        self.log_event(&message);
+        *self.input_error.lock().unwrap() = Some(message);
+    }
+    
+    /// Checks an input sent by the clicking thread. Each failure in a row
+    /// backs the thread off twice as long before it retries, and clicking
+    /// stops after `INPUT_FAILURE_LIMIT` of them rather than spinning on an
+    /// injection that keeps failing. Returns whether the input went through.
+    fn report_input(&self, result: Result<(), rdev::SimulateError>) -> bool {
+        let Err(error) = result else {
+            *self.input_failures.lock().unwrap() = 0;
+            return true;
+        };
+        self.record_input_error(&error);
+        
+        let failures = {
+            let mut failures = self.input_failures.lock().unwrap();
+            *failures += 1;
+            *failures
+        };
+        if failures >= INPUT_FAILURE_LIMIT {
+            *self.input_failures.lock().unwrap() = 0;
+            self.panic_stop();
+            self.log_event(&format!("Stopped clicking after {} failed inputs in a row", failures));
+        } else {
+            thread::sleep(INPUT_RETRY_BACKOFF * 2u32.pow(failures - 1));
+        }
+        false
+    }
+    
+    fn hotkey_for_slot(&self, slot: HotkeySlot) -> &Arc<Mutex<Vec<HotkeyInput>>> {
+        match slot {
+            HotkeySlot::Activation => &self.hotkey,
+            HotkeySlot::Start => &self.start_hotkey,
+            HotkeySlot::Stop => &self.stop_hotkey,
+            HotkeySlot::Panic => &self.panic_hotkey,
+            HotkeySlot::ProfileSwitch => &self.profile_switch_hotkey,
+            HotkeySlot::PickTarget => &self.pick_target_hotkey,
+        }
+    }
+    
+    fn finish_hotkey_capture(&self, combination: Vec<HotkeyInput>) {
+        let slot = *self.capture_slot.lock().unwrap();
+        *self.hotkey_for_slot(slot).lock().unwrap() = combination;
+        *self.listening_text.lock().unwrap() = String::new();
+        self.capturing_hotkey.store(false, Ordering::SeqCst);
+        *self.current_combination.lock().unwrap() = Vec::new();
+        self.save_current_config();
+    }
+    
+    fn hotkey_row(&self, ui: &mut egui::Ui, label: &str, slot: HotkeySlot) {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            
+            let capturing_this_slot = self.capturing_hotkey.load(Ordering::SeqCst)
+                && *self.capture_slot.lock().unwrap() == slot;
+            
+            let button_text = if capturing_this_slot {
+                let combination = self.current_combination.lock().unwrap();
+                combination_to_string(&combination)
+            } else {
+                let hotkey = self.hotkey_for_slot(slot).lock().unwrap();
+                if hotkey.is_empty() {
+                    "None".to_string()
+                } else {
+                    combination_to_string(&hotkey)
+                }
+            };
+            
+            if ui.button(&button_text).clicked() {
+                *self.capture_slot.lock().unwrap() = slot;
+                self.capturing_hotkey.store(true, Ordering::SeqCst);
+                *self.listening_text.lock().unwrap() = "Press and hold keys or mouse buttons, release to confirm...".to_string();
+                *self.current_combination.lock().unwrap() = Vec::new();
+            }
+            
+            if slot != HotkeySlot::Activation && ui.button("Clear").clicked() {
+                self.hotkey_for_slot(slot).lock().unwrap().clear();
+                self.save_current_config();
+            }
+        });
+    }
+}
+
+impl eframe::App for PyladeClickerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let should_refresh = {
+            let now = Instant::now();
+            let last_refresh = self.last_window_refresh.lock().unwrap();
+            now.duration_since(*last_refresh) >= Duration::from_secs(2)
+        };
+        
+        self.record_foreground_window();
+        
+        // The active profile can change off the GUI thread, from a switch key or
+        // a config reload, so the Name field follows it here
+        let active = self.active_profile.lock().unwrap().clone();
+        if active != self.profile_name_edit_for {
+            self.profile_name_edit = active.clone();
+            self.profile_name_edit_for = active;
+        }
+        
+        if should_refresh {
+            self.refresh_windows();
+            self.apply_auto_rules();
+            *self.last_window_refresh.lock().unwrap() = Instant::now();
+        }
+        
+        let captured = self.captured_hotkey.lock().unwrap().take();
+        if let Some(combination) = captured {
+            self.finish_hotkey_capture(combination);
+        }
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Pylade Clicker");
+            
+            let config_error = self.config_error.lock().unwrap().clone();
+            if let Some(config_error) = config_error {
+                ui.colored_label(egui::Color32::RED, "Problems were found in config.json and corrected:");
+                ui.colored_label(egui::Color32::RED, config_error);
+                if ui.button("Dismiss").clicked() {
+                    *self.config_error.lock().unwrap() = None;
+                }
+            }
+            
+            let input_error = self.input_error.lock().unwrap().clone();
+            if let Some(input_error) = input_error {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, input_error);
+                    if ui.button("Dismiss").clicked() {
+                        *self.input_error.lock().unwrap() = None;
+                    }
+                });
+            }
+            
+            let external_config_error = self.external_config_error.lock().unwrap().clone();
+            if let Some(external_config_error) = external_config_error {
+                ui.colored_label(egui::Color32::RED, "config.json was changed outside the app but could not be read, so the change was ignored:");
+                ui.colored_label(egui::Color32::RED, external_config_error);
+                if ui.button("Dismiss").clicked() {
+                    *self.external_config_error.lock().unwrap() = None;
+                }
+            }
+            
+            let config_save_error = self.config_save_error.lock().unwrap().clone();
+            if let Some(config_save_error) = config_save_error {
+                ui.colored_label(egui::Color32::RED, config_save_error);
+            }
+            
+            ui.small(format!("Config: {}", get_config_path().display()));
+            
+            if self.external_config.lock().unwrap().is_some() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "config.json was changed outside the app while you had unsaved changes.",
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Load File").clicked() {
+                        let external = self.external_config.lock().unwrap().take();
+                        if let Some(config) = external {
+                            *self.save_requested.lock().unwrap() = None;
+                            self.apply_config(&config);
+                            self.profile_name_edit = self.active_profile.lock().unwrap().clone();
+                        }
+                    }
+                    if ui.button("Keep Mine").clicked() {
+                        *self.external_config.lock().unwrap() = None;
+                        self.save_current_config();
+                    }
+                });
+            }
+            
+            ui.separator();
+            
+            ui.horizontal(|ui| {
+                ui.label("Profile:");
+                let active = self.active_profile.lock().unwrap().clone();
+                let names: Vec<String> = self.profiles.lock().unwrap().iter().map(|p| p.name.clone()).collect();
+                let mut selected = active.clone();
+                egui::ComboBox::from_id_source("profile_selector")
+                    .selected_text(&selected)
+                    .show_ui(ui, |ui| {
+                        for name in &names {
+                            ui.selectable_value(&mut selected, name.clone(), name);
+                        }
+                    });
+                
+                if selected != active {
+                    self.switch_profile(&selected);
+                    self.profile_name_edit = selected;
+                }
+                
+                if ui.button("Duplicate").clicked() {
+                    self.duplicate_profile();
+                }
+                
+                if ui.add_enabled(names.len() > 1, egui::Button::new("Delete")).clicked() {
+                    self.delete_profile();
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut self.profile_name_edit);
+                if ui.button("Rename").clicked() {
+                    self.rename_profile();
+                }
+            });
+            
+            self.hotkey_row(ui, "Switch Key:", HotkeySlot::ProfileSwitch);
+            
+            ui.separator();
+            
+            ui.horizontal(|ui| {
+                ui.label("Status:");
+                if self.input_paused.load(Ordering::SeqCst) {
+                    ui.colored_label(egui::Color32::YELLOW, "PAUSED - user input");
+                } else if self.clicking.load(Ordering::SeqCst) {
+                    match *self.target_state.lock().unwrap() {
+                        TargetState::Found if self.condition_waiting.load(Ordering::SeqCst) => {
+                            ui.colored_label(egui::Color32::YELLOW, "WAITING - click condition not met")
+                        }
+                        TargetState::Found => ui.colored_label(egui::Color32::GREEN, "CLICKING"),
+                        TargetState::Paused => ui.colored_label(egui::Color32::YELLOW, "PAUSED - target window lost"),
+                        TargetState::GlobalFallback => {
+                            ui.colored_label(egui::Color32::YELLOW, "CLICKING GLOBALLY - target window lost")
+                        }
+                    };
+                } else {
+                    ui.colored_label(egui::Color32::RED, "STOPPED");
+                }
+            });
+            
+            if !self.clicker_armed.load(Ordering::SeqCst) {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, "Disarmed by auto-profile rule");
+                    if ui.button("Re-arm").clicked() {
+                        self.clicker_armed.store(true, Ordering::SeqCst);
+                        self.disarmed_by_rule = false;
+                    }
+                });
+            }
+            
+            if !self.hotkeys_armed.load(Ordering::SeqCst) {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, "Panic key pressed - hotkeys disabled");
+                    if ui.button("Re-arm Hotkeys").clicked() {
+                        self.hotkeys_armed.store(true, Ordering::SeqCst);
+                    }
+                });
+            }
+            
+            ui.horizontal(|ui| {
+                if self.clicking.load(Ordering::SeqCst) {
+                    if ui.button("Stop Clicking").clicked() {
+                        self.clicking.store(false, Ordering::SeqCst);
+                    }
+                } else {
+                    if ui.add_enabled(self.clicker_armed.load(Ordering::SeqCst), egui::Button::new("Start Clicking")).clicked() {
+                        self.start_clicking();
+                    }
+                }
+                });
+
+            ui.separator();
+
+            self.hotkey_row(ui, "Hotkey:", HotkeySlot::Activation);
+            self.hotkey_row(ui, "Start Key:", HotkeySlot::Start);
+            self.hotkey_row(ui, "Stop Key:", HotkeySlot::Stop);
+            self.hotkey_row(ui, "Panic Key:", HotkeySlot::Panic);
+            
+            self.failsafe_ui(ui);
+            
+            {
+                let listening_text = self.listening_text.lock().unwrap();
+                if !listening_text.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::YELLOW, &*listening_text);
+                        if ui.button("Cancel").clicked() {
+                            self.capturing_hotkey.store(false, Ordering::SeqCst);
+                            *self.current_combination.lock().unwrap() = Vec::new();
+                            drop(listening_text);
+                            *self.listening_text.lock().unwrap() = String::new();
+                        }
+                    });
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Activation:");
+                let mut current_style = self.activation_style.lock().unwrap().clone();
+                let changed_toggle = ui.radio_value(&mut current_style, ActivationStyle::Toggle, "Toggle").changed();
+                let changed_hold = ui.radio_value(&mut current_style, ActivationStyle::HoldToRun, "Hold to Run").changed();
+                let changed_one_shot = ui.radio_value(&mut current_style, ActivationStyle::OneShot, "One-Shot").changed();
+                
+                if changed_toggle || changed_hold || changed_one_shot {
+                    *self.activation_style.lock().unwrap() = current_style;
+                    self.save_current_config();
+                }
+            });
+            
+            if *self.activation_style.lock().unwrap() == ActivationStyle::OneShot {
+                ui.horizontal(|ui| {
+                    ui.label("Clicks per activation:");
+                    let mut clicks = *self.one_shot_clicks.lock().unwrap();
+                    if ui.add(egui::DragValue::new(&mut clicks).clamp_range(1..=10000)).changed() {
+                        *self.one_shot_clicks.lock().unwrap() = clicks;
+                        self.save_current_config();
+                    }
+                    if *self.click_mode.lock().unwrap() == ClickMode::Hold {
+                        ui.small("(Hold mode holds for this many click delays)");
+                    }
+                });
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Click Mode:");
+                let mut current_mode = self.click_mode.lock().unwrap().clone();
+                let changed_click = ui.radio_value(&mut current_mode, ClickMode::Click, "Click").changed();
+                let changed_hold = ui.radio_value(&mut current_mode, ClickMode::Hold, "Hold").changed();
+                let changed_humanized = ui.radio_value(&mut current_mode, ClickMode::Humanized, "Humanized").changed();
+                
+                if changed_click || changed_hold || changed_humanized {
+                    *self.click_mode.lock().unwrap() = current_mode;
+                    self.save_current_config();
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Click Type:");
+                let mut current_type = self.click_type.lock().unwrap().clone();
+                let changed_left = ui.radio_value(&mut current_type, ClickType::LeftClick, "Left Click").changed();
+                let changed_right = ui.radio_value(&mut current_type, ClickType::RightClick, "Right Click").changed();
+                let changed_space = ui.radio_value(&mut current_type, ClickType::Space, "Space").changed();
+                
+                if changed_left || changed_right || changed_space {
+                    *self.click_type.lock().unwrap() = current_type;
+                    self.save_current_config();
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                let mut pause_on_input = self.pause_on_input.load(Ordering::SeqCst);
+                if ui.checkbox(&mut pause_on_input, "Pause global clicking on my input").changed() {
+                    self.pause_on_input.store(pause_on_input, Ordering::SeqCst);
+                    self.save_current_config();
+                }
+                
+                if pause_on_input {
+                    ui.label("Resume after (ms):");
+                    let mut idle_ms = self.pause_idle.lock().unwrap().as_millis() as u64;
+                    if ui.add(egui::DragValue::new(&mut idle_ms).clamp_range(PAUSE_IDLE_RANGE_MS)).changed() {
+                        *self.pause_idle.lock().unwrap() = Duration::from_millis(idle_ms);
+                        self.save_current_config();
+                    }
+                }
+            });
+            
+            let current_mode = self.click_mode.lock().unwrap().clone();
+            let one_shot_hold = current_mode == ClickMode::Hold
+                && *self.activation_style.lock().unwrap() == ActivationStyle::OneShot;
+            if current_mode == ClickMode::Click || one_shot_hold {
+                ui.horizontal(|ui| {
+                    ui.label("Delay (ms):");
+                    let mut delay = self.normal_delay.lock().unwrap().as_millis() as f32;
+                    if ui.add(egui::Slider::new(&mut delay, 1.0..=1000.0)).changed() {
+                        *self.normal_delay.lock().unwrap() = Duration::from_millis(delay as u64);
+                        self.save_current_config();
+                    }
+                });
+            }
+            
+            if current_mode == ClickMode::Humanized {
+                ui.horizontal(|ui| {
+                    ui.label("CPS:");
+                    let mut cps = *self.cps.lock().unwrap();
+                    if ui.add(egui::Slider::new(&mut cps, CPS_RANGE)).changed() {
+                        *self.cps.lock().unwrap() = cps;
+                        self.save_current_config();
+                    }
+                });
+                
+                if *self.cps.lock().unwrap() > 50.0 {
+                    ui.label("Burst mode enabled for very high CPS");
+                }
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Refresh Windows").clicked() {
+                    self.refresh_windows();
+                }
+                
+                if ui.button("Clear Targets").clicked() {
+                    self.targets.lock().unwrap().clear();
+                    self.save_current_config();
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                let picker = ui.add(egui::Button::new("Drag to Target").sense(egui::Sense::drag()))
+                    .on_hover_text("Drag onto any window to add it as a target");
+                if picker.dragged() {
+                    ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
+                }
+                if picker.drag_stopped() {
+                    self.pick_target_at_cursor();
+                }
+                
+                let mut pick_point = self.pick_point.load(Ordering::SeqCst);
+                if ui.checkbox(&mut pick_point, "Click where I point").changed() {
+                    self.pick_point.store(pick_point, Ordering::SeqCst);
+                }
+            });
+            
+            self.hotkey_row(ui, "Pick Key:", HotkeySlot::PickTarget);
+            
+            if !self.windows.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label("Available Windows:");
+                    ui.add(egui::TextEdit::singleline(&mut self.window_filter).hint_text("Search title, exe, class or PID"));
+                });
+                
+                let filter = self.window_filter.trim().to_lowercase();
+                
+                for window in &self.windows {
+                    self.window_icons.entry(window.hwnd.0).or_insert_with(|| {
+                        let (size, rgba) = window_icon(window.hwnd)?;
+                        let image = egui::ColorImage::from_rgba_unmultiplied(size, &rgba);
+                        Some(ctx.load_texture(format!("window_icon_{}", window.hwnd.0), image, Default::default()))
+                    });
+                }
+                
+                egui::Frame::none()
+                    .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY))
+                    .inner_margin(egui::Margin::same(8.0))
+                    .outer_margin(egui::Margin::same(4.0))
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            for window in &self.windows {
+                                let details = format!("{} | PID {} | {}", window.exe_name, window.pid, window.class_name);
+                                if !filter.is_empty()
+                                    && !window.title.to_lowercase().contains(&filter)
+                                    && !details.to_lowercase().contains(&filter)
+                                {
+                                    continue;
+                                }
+                                
+                                let rule = WindowMatchRule {
+                                    kind: "ExactTitle".to_string(),
+                                    pattern: window.title.clone(),
+                                };
+                                let is_selected = self.targets.lock().unwrap().iter().any(|t| t.rule == rule);
+                                ui.horizontal(|ui| {
+                                    if let Some(Some(icon)) = self.window_icons.get(&window.hwnd.0) {
+                                        ui.image((icon.id(), egui::vec2(16.0, 16.0)));
+                                    } else {
+                                        let gap = 16.0 + ui.spacing().item_spacing.x;
+                                        ui.add_space(gap);
+                                    }
+                                    if ui.selectable_label(is_selected, &window.title).clicked() {
+                                        self.toggle_target(rule);
+                                        self.save_current_config();
+                                    }
+                                });
+                                ui.small(details);
+                            }
+                        });
+                    });
+            }
+            
+            self.targets_ui(ui);
+            
+            ui.separator();
+            
+            self.auto_rules_ui(ui);
+            
+            self.bundle_ui(ui, ctx);
+            
+            self.session_log_ui(ui);
+        });
+        
+        ctx.request_repaint_after(Duration::from_millis(16));
+    }
+    
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.clicking.store(false, Ordering::SeqCst);
+        self.is_holding.store(false, Ordering::SeqCst);
+        release_tracked_inputs();
+        
+        if self.save_requested.lock().unwrap().take().is_some() {
+            self.flush_config();
+        }
+    }
+}
+
+impl PyladeClickerApp {
+    /// Lists the windows a user would want to target, most recently focused
+    /// first and the rest in z-order.
+    fn refresh_windows(&mut self) {
+        let mut windows: Vec<WindowInfo> = top_level_windows(true).into_iter()
+            .filter(is_listable_window)
+            .collect();
+        
+        let history = &self.foreground_history;
+        windows.sort_by_key(|w| history.iter().position(|&hwnd| hwnd == w.hwnd.0).unwrap_or(usize::MAX));
+        self.window_icons.retain(|&hwnd, _| windows.iter().any(|w| w.hwnd.0 == hwnd));
+        self.windows = windows;
+    }
+    
+    fn record_foreground_window(&mut self) {
+        let hwnd = unsafe { GetForegroundWindow() }.0;
+        if hwnd == 0 || self.foreground_history.first() == Some(&hwnd) {
+            return;
+        }
+        
+        self.foreground_history.retain(|&h| h != hwnd);
+        self.foreground_history.insert(0, hwnd);
+        self.foreground_history.truncate(FOREGROUND_HISTORY_LEN);
+    }
+    
+    /// Runs the auto-profile rules against the foreground window. Rules only
+    /// fire when the foreground window changes, so a manual profile switch
+    /// sticks until the user moves to another window.
+    fn apply_auto_rules(&mut self) {
+        let Some((hwnd, title, executable)) = foreground_window_info() else {
+            return;
+        };
+        
+        let foreground = (hwnd.0, title.clone());
+        if self.last_foreground.as_ref() == Some(&foreground) {
+            return;
+        }
+        self.last_foreground = Some(foreground);
+        
+        let rule = self.auto_rules.lock().unwrap().iter()
+            .find(|rule| auto_rule_matches(rule, &title, &executable))
+            .cloned();
+        
+        // A rule's disarm only lasts while a window it matches is in front
+        if self.disarmed_by_rule && rule.as_ref().is_none_or(|rule| rule.action != "Disarm") {
+            self.disarmed_by_rule = false;
+            self.clicker_armed.store(true, Ordering::SeqCst);
+        }
+        
+        if let Some(rule) = rule {
+            self.switch_profile(&rule.profile);
+            self.profile_name_edit = self.active_profile.lock().unwrap().clone();
+            
+            match rule.action.as_str() {
+                "Arm" => self.clicker_armed.store(true, Ordering::SeqCst),
+                "Disarm" => {
+                    self.clicker_armed.store(false, Ordering::SeqCst);
+                    self.disarmed_by_rule = true;
+                    self.panic_stop();
+                }
+                _ => {}
+            }
+        }
+    }
+    
+    fn export_bundle(&self) -> Result<String, String> {
+        self.store_active_profile();
+        let profiles: Vec<ProfileConfig> = self.profiles.lock().unwrap().iter()
+            .filter(|p| self.export_selection.contains(&p.name))
+            .cloned()
+            .collect();
+        
+        if profiles.is_empty() {
+            return Err("Select at least one profile to export".to_string());
+        }
+        
+        let bundle = ProfileBundle { bundle_version: BUNDLE_VERSION, profiles };
+        serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())
+    }
+    
+    /// Parses and validates a bundle, then queues its profiles for import so
+    /// name conflicts can be resolved before anything is changed.
+    fn begin_import(&mut self, bundle_json: &str) {
+        let bundle = match parse_bundle(bundle_json) {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                self.bundle_status = Some(format!("Not a valid profile bundle: {}", e));
+                return;
+            }
+        };
+        
+        if bundle.bundle_version > BUNDLE_VERSION {
+            self.bundle_status = Some(format!(
+                "Bundle version {} is newer than this build supports",
+                bundle.bundle_version
+            ));
+            return;
+        }
+        
+        let mut imported = AppConfig {
+            active_profile: bundle.profiles.first().map(|p| p.name.clone()).unwrap_or_default(),
+            profiles: bundle.profiles,
+            ..AppConfig::default()
+        };
+        let errors = validate_config(&mut imported);
+        
+        let mut taken: Vec<String> = self.profiles.lock().unwrap().iter().map(|p| p.name.clone()).collect();
+        self.pending_import = imported.profiles.into_iter()
+            .map(|profile| {
+                let conflict = taken.contains(&profile.name);
+                let mut name = profile.name.clone();
+                let mut n = 2;
+                while taken.contains(&name) {
+                    name = format!("{} {}", profile.name, n);
+                    n += 1;
+                }
+                taken.push(name.clone());
+                PendingImport { profile, name, conflict, overwrite: false }
+            })
+            .collect();
+        
+        self.bundle_status = if errors.is_empty() {
+            None
+        } else {
+            Some(format!("Corrected while importing:\n{}", errors.join("\n")))
+        };
+    }
+    
+    fn finish_import(&mut self) {
+        let existing: Vec<String> = self.profiles.lock().unwrap().iter().map(|p| p.name.clone()).collect();
+        let mut names: Vec<String> = Vec::new();
+        for pending in &self.pending_import {
+            let name = if pending.overwrite { &pending.profile.name } else { &pending.name };
+            let name = name.trim();
+            if name.is_empty() || names.iter().any(|n| n == name) || (!pending.overwrite && existing.iter().any(|n| n == name)) {
+                self.bundle_status = Some(format!("Profile name \"{}\" is empty or already taken", name));
+                return;
+            }
+            names.push(name.to_string());
+        }
+        
+        self.store_active_profile();
+        let active = self.active_profile.lock().unwrap().clone();
+        let mut reload_active = None;
+        {
+            let mut profiles = self.profiles.lock().unwrap();
+            for pending in self.pending_import.drain(..) {
+                let mut profile = pending.profile;
+                if pending.overwrite {
+                    if profile.name == active {
+                        reload_active = Some(profile.clone());
+                    }
+                    if let Some(existing) = profiles.iter_mut().find(|p| p.name == profile.name) {
+                        *existing = profile;
+                    }
+                } else {
+                    profile.name = pending.name.trim().to_string();
+                    profiles.push(profile);
+                }
+            }
+        }
+        
+        if let Some(profile) = reload_active {
+            self.panic_stop();
+            self.apply_profile(&profile);
+        }
+        
+        self.bundle_status = Some(format!("Imported {} profile(s)", names.len()));
+        self.save_current_config();
+    }
+    
+    fn bundle_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        egui::CollapsingHeader::new("Import / Export Profiles").show(ui, |ui| {
+            let names: Vec<String> = self.profiles.lock().unwrap().iter().map(|p| p.name.clone()).collect();
+            self.export_selection.retain(|n| names.contains(n));
+            
+            ui.label("Export:");
+            ui.horizontal_wrapped(|ui| {
+                for name in &names {
+                    let mut selected = self.export_selection.contains(name);
+                    if ui.checkbox(&mut selected, name).changed() {
+                        if selected {
+                            self.export_selection.push(name.clone());
+                        } else {
+                            self.export_selection.retain(|n| n != name);
+                        }
+                    }
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut self.bundle_path);
+            });
+            
+            ui.horizontal(|ui| {
+                if ui.button("Copy to Clipboard").clicked() {
+                    match self.export_bundle() {
+                        Ok(bundle_json) => {
+                            ctx.output_mut(|o| o.copied_text = bundle_json);
+                            self.bundle_status = Some("Bundle copied to clipboard".to_string());
+                        }
+                        Err(e) => self.bundle_status = Some(e),
+                    }
+                }
+                
+                if ui.button("Save to File").clicked() {
+                    let result = self.export_bundle().and_then(|bundle_json| {
+                        fs::write(self.bundle_path.trim(), bundle_json).map_err(|e| e.to_string())
+                    });
+                    self.bundle_status = Some(match result {
+                        Ok(()) => format!("Bundle saved to {}", self.bundle_path.trim()),
+                        Err(e) => format!("Export failed: {}", e),
+                    });
+                }
+            });
+            
+            ui.separator();
+            
+            ui.label("Import (paste a bundle or load it from the file above):");
+            ui.add(egui::TextEdit::multiline(&mut self.import_text).desired_rows(3));
+            
+            ui.horizontal(|ui| {
+                if ui.button("Import Pasted").clicked() {
+                    let bundle_json = self.import_text.clone();
+                    self.begin_import(&bundle_json);
+                }
+                
+                if ui.button("Import File").clicked() {
+                    match fs::read_to_string(self.bundle_path.trim()) {
+                        Ok(bundle_json) => self.begin_import(&bundle_json),
+                        Err(e) => self.bundle_status = Some(format!("Import failed: {}", e)),
+                    }
+                }
+            });
+            
+            if !self.pending_import.is_empty() {
+                for (index, pending) in self.pending_import.iter_mut().enumerate() {
+                    ui.push_id(index, |ui| {
+                        ui.horizontal(|ui| {
+                            if pending.conflict {
+                                ui.colored_label(egui::Color32::YELLOW, format!("\"{}\" exists:", pending.profile.name));
+                                ui.checkbox(&mut pending.overwrite, "Overwrite");
+                                if !pending.overwrite {
+                                    ui.label("import as");
+                                    ui.text_edit_singleline(&mut pending.name);
+                                }
+                            } else {
+                                ui.label(format!("\"{}\"", pending.profile.name));
+                            }
+                        });
+                    });
+                }
+                
+                ui.horizontal(|ui| {
+                    if ui.button("Confirm Import").clicked() {
+                        self.finish_import();
+                    }
+                    if ui.button("Cancel Import").clicked() {
+                        self.pending_import.clear();
+                        self.bundle_status = None;
+                    }
+                });
+            }
+            
+            if let Some(status) = &self.bundle_status {
+                ui.label(status);
+            }
+        });
+    }
+    
+    fn failsafe_ui(&self, ui: &mut egui::Ui) {
+        let mut zone = *self.failsafe_zone.lock().unwrap();
+        let mut rect = *self.failsafe_rect.lock().unwrap();
+        let mut changed = false;
+        
+        ui.horizontal(|ui| {
+            ui.label("Failsafe:");
+            egui::ComboBox::from_id_source("failsafe_zone")
+                .selected_text(match zone {
+                    FailsafeZone::Off => "Off",
+                    FailsafeZone::TopLeft => "Top-left corner",
+                    FailsafeZone::TopRight => "Top-right corner",
+                    FailsafeZone::BottomLeft => "Bottom-left corner",
+                    FailsafeZone::BottomRight => "Bottom-right corner",
+                    FailsafeZone::Rect => "Rectangle",
+                })
+                .show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(&mut zone, FailsafeZone::Off, "Off").changed();
+                    changed |= ui.selectable_value(&mut zone, FailsafeZone::TopLeft, "Top-left corner").changed();
+                    changed |= ui.selectable_value(&mut zone, FailsafeZone::TopRight, "Top-right corner").changed();
+                    changed |= ui.selectable_value(&mut zone, FailsafeZone::BottomLeft, "Bottom-left corner").changed();
+                    changed |= ui.selectable_value(&mut zone, FailsafeZone::BottomRight, "Bottom-right corner").changed();
+                    changed |= ui.selectable_value(&mut zone, FailsafeZone::Rect, "Rectangle").changed();
+                });
+        });
+        
+        if zone == FailsafeZone::Rect {
+            ui.horizontal(|ui| {
+                let [left, top, right, bottom] = &mut rect;
+                ui.label("Left/Top:");
+                changed |= ui.add(egui::DragValue::new(left).clamp_range(i32::MIN..=*right - 1)).changed();
+                changed |= ui.add(egui::DragValue::new(top).clamp_range(i32::MIN..=*bottom - 1)).changed();
+                ui.label("Right/Bottom:");
+                changed |= ui.add(egui::DragValue::new(right).clamp_range(*left + 1..=i32::MAX)).changed();
+                changed |= ui.add(egui::DragValue::new(bottom).clamp_range(*top + 1..=i32::MAX)).changed();
+            });
+        }
+        
+        if changed {
+            *self.failsafe_zone.lock().unwrap() = zone;
+            *self.failsafe_rect.lock().unwrap() = rect;
+            self.save_current_config();
+        }
+    }
+    
+    fn session_log_ui(&self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Session Log").show(ui, |ui| {
+            let mut session_log = self.session_log.lock().unwrap();
+            
+            egui::ScrollArea::vertical()
+                .id_source("session_log")
+                .max_height(150.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for entry in session_log.iter() {
+                        ui.small(entry);
+                    }
+                });
+            
+            if ui.button("Clear Log").clicked() {
+                session_log.clear();
+            }
+        });
+    }
+    
+    /// Adds a target for `rule`, seeded with the profile's current schedule,
+    /// or removes it if it is already targeted.
+    fn toggle_target(&self, rule: WindowMatchRule) {
+        let target = self.new_target(rule.clone(), None);
+        let mut targets = self.targets.lock().unwrap();
+        if let Some(index) = targets.iter().position(|t| t.rule == rule) {
+            targets.remove(index);
+        } else {
+            targets.push(target);
+        }
+    }
+    
+    fn new_target(&self, rule: WindowMatchRule, point: Option<(i32, i32)>) -> ClickTarget {
+        let profile = self.current_profile();
+        ClickTarget {
+            rule,
+            child: None,
+            point,
+            click_mode: profile.click_mode,
+            normal_delay_ms: profile.normal_delay_ms,
+            cps: profile.cps,
+        }
+    }
+    
+    /// Makes the window under the cursor a target, and the cursor position its
+    /// click point if `pick_point` is set. A window that is already a target
+    /// only has its point updated.
+    fn pick_target_at_cursor(&self) {
+        let Some((hwnd, cursor)) = window_at_cursor() else {
+            return;
+        };
+        
+        let title = window_text(hwnd);
+        let rule = if title.is_empty() {
+            WindowMatchRule { kind: "Class".to_string(), pattern: window_class_name(hwnd) }
+        } else {
+            WindowMatchRule { kind: "ExactTitle".to_string(), pattern: title }
+        };
+        let point = self.pick_point.load(Ordering::SeqCst).then_some((cursor.x, cursor.y));
+        let target = self.new_target(rule, point);
+        
+        {
+            let mut targets = self.targets.lock().unwrap();
+            match targets.iter_mut().find(|t| t.rule == target.rule) {
+                Some(existing) => existing.point = point,
+                None => targets.push(target),
+            }
+        }
+        self.save_current_config();
+    }
+    
+    /// Lists the targets so their exact titles can be loosened into other
+    /// kinds of match rules, along with each target's own schedule when the
+    /// strategy runs them independently.
+    fn targets_ui(&mut self, ui: &mut egui::Ui) {
+        let mut targets = self.targets.lock().unwrap().clone();
+        if targets.is_empty() {
+            ui.label("Target: none (clicking globally)");
+            return;
+        }
+        
+        let mut strategy = self.target_strategy.lock().unwrap().clone();
+        let mut loss_action = self.target_loss_action.lock().unwrap().clone();
+        let mut condition = self.click_condition.lock().unwrap().clone();
+        let mut changed = false;
+        let mut remove = None;
+        let mut close_picker = false;
+        
+        ui.horizontal(|ui| {
+            ui.label("Strategy:");
+            changed |= ui.radio_value(&mut strategy, TargetStrategy::RoundRobin, "Round-robin").changed();
+            changed |= ui.radio_value(&mut strategy, TargetStrategy::AllAtOnce, "All at once").changed();
+            changed |= ui.radio_value(&mut strategy, TargetStrategy::Independent, "Independent").changed();
+        });
+        
+        ui.horizontal(|ui| {
+            ui.label("If lost:");
+            changed |= ui.radio_value(&mut loss_action, TargetLossAction::Stop, "Stop").changed();
+            changed |= ui.radio_value(&mut loss_action, TargetLossAction::Pause, "Pause").changed();
+            changed |= ui.radio_value(&mut loss_action, TargetLossAction::Global, "Click globally").changed();
+        });
+        
+        ui.horizontal(|ui| {
+            ui.label("Click only:");
+            changed |= ui.radio_value(&mut condition, ClickCondition::Always, "Always").changed();
+            changed |= ui.radio_value(&mut condition, ClickCondition::Foreground, "While focused").changed();
+            changed |= ui.radio_value(&mut condition, ClickCondition::Background, "While in background").changed();
+            changed |= ui.radio_value(&mut condition, ClickCondition::CursorInside, "While cursor inside").changed();
+        });
+        
+        for (index, target) in targets.iter_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Target:");
+                    egui::ComboBox::from_id_source("target_rule_kind")
+                        .selected_text(window_match_kind_label(&target.rule.kind))
+                        .show_ui(ui, |ui| {
+                            for kind in WINDOW_MATCH_KINDS {
+                                changed |= ui.selectable_value(&mut target.rule.kind, kind.to_string(), window_match_kind_label(kind)).changed();
+                            }
+                        });
+                    changed |= ui.text_edit_singleline(&mut target.rule.pattern).changed();
+                    
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                });
+                
+                if target.rule.kind == "TitleRegex" && Regex::new(&target.rule.pattern).is_err() {
+                    ui.colored_label(egui::Color32::RED, "Invalid regex");
                 }
-            });
-        }
-        
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Pylade Clicker");
-            
-            ui.separator();
-            
-            ui.horizontal(|ui| {
-                ui.label("Status:");
-                if self.clicking.load(Ordering::SeqCst) {
-                    ui.colored_label(egui::Color32::GREEN, "CLICKING");
-                } else {
-                    ui.colored_label(egui::Color32::RED, "STOPPED");
-                }
-            });
-            
-            ui.horizontal(|ui| {
-                if self.clicking.load(Ordering::SeqCst) {
-                    if ui.button("Stop Clicking").clicked() {
-                        self.clicking.store(false, Ordering::SeqCst);
+                
+                ui.horizontal(|ui| {
+                    match &target.child {
+                        Some(child) => ui.label(format!("Control: {} #{} \"{}\"", child.class_name, child.index + 1, child.text)),
+                        None => ui.label("Control: whole window"),
+                    };
+                    
+                    if ui.button("Pick Control").clicked() {
+                        self.control_picker = match find_target_window(&target.rule) {
+                            Some(hwnd) => Some((index, child_windows(hwnd))),
+                            None => Some((index, Vec::new())),
+                        };
                     }
-                } else {
-                    if ui.button("Start Clicking").clicked() {
-                        self.clicking.store(true, Ordering::SeqCst);
+                    
+                    if target.child.is_some() && ui.button("Whole Window").clicked() {
+                        target.child = None;
+                        changed = true;
                     }
-                }
                 });
-
-            ui.separator();
-
-            ui.horizontal(|ui| {
-                ui.label("Hotkey:");
                 
-                let button_text = if self.capturing_hotkey.load(Ordering::SeqCst) {
-                    let combination = self.current_combination.lock().unwrap();
-                    combination_to_string(&*combination)
-                } else {
-                    let hotkey = self.hotkey.lock().unwrap();
-                    combination_to_string(&*hotkey)
-                };
-                
-                if ui.button(&button_text).clicked() {
-                    self.capturing_hotkey.store(true, Ordering::SeqCst);
-                    *self.listening_text.lock().unwrap() = "Press and hold keys, release to confirm...".to_string();
-                    *self.current_combination.lock().unwrap() = Vec::new();
-                }
-            });
-            
-            {
-                let listening_text = self.listening_text.lock().unwrap();
-                if !listening_text.is_empty() {
+                if let Some((x, y)) = target.point {
                     ui.horizontal(|ui| {
-                        ui.colored_label(egui::Color32::YELLOW, &*listening_text);
-                        if ui.button("Cancel").clicked() {
-                            self.capturing_hotkey.store(false, Ordering::SeqCst);
-                            drop(listening_text);
-                            *self.listening_text.lock().unwrap() = String::new();
+                        ui.label(format!("Point: ({}, {})", x, y));
+                        if ui.button("Centre").clicked() {
+                            target.point = None;
+                            changed = true;
                         }
                     });
                 }
-            }
-
-            ui.separator();
-
-            ui.horizontal(|ui| {
-                ui.label("Click Mode:");
-                let mut current_mode = self.click_mode.lock().unwrap().clone();
-                let changed_click = ui.radio_value(&mut current_mode, ClickMode::Click, "Click").changed();
-                let changed_hold = ui.radio_value(&mut current_mode, ClickMode::Hold, "Hold").changed();
-                let changed_humanized = ui.radio_value(&mut current_mode, ClickMode::Humanized, "Humanized").changed();
                 
-                if changed_click || changed_hold || changed_humanized {
-                    *self.click_mode.lock().unwrap() = current_mode;
-                    self.save_current_config();
+                if let Some((picker_index, controls)) = &self.control_picker {
+                    if *picker_index == index {
+                        if let Some(child) = control_picker_ui(ui, controls, &mut close_picker) {
+                            target.child = Some(child);
+                            close_picker = true;
+                            changed = true;
+                        }
+                    }
                 }
-            });
-            
-            ui.horizontal(|ui| {
-                ui.label("Click Type:");
-                let mut current_type = self.click_type.lock().unwrap().clone();
-                let changed_left = ui.radio_value(&mut current_type, ClickType::LeftClick, "Left Click").changed();
-                let changed_right = ui.radio_value(&mut current_type, ClickType::RightClick, "Right Click").changed();
-                let changed_space = ui.radio_value(&mut current_type, ClickType::Space, "Space").changed();
                 
-                if changed_left || changed_right || changed_space {
-                    *self.click_type.lock().unwrap() = current_type;
-                    self.save_current_config();
+                if strategy == TargetStrategy::Independent {
+                    ui.horizontal(|ui| {
+                        ui.label("Mode:");
+                        for mode in CLICK_MODES {
+                            changed |= ui.radio_value(&mut target.click_mode, mode.to_string(), *mode).changed();
+                        }
+                        
+                        match target.click_mode.as_str() {
+                            "Click" => {
+                                ui.label("Delay (ms):");
+                                changed |= ui.add(egui::DragValue::new(&mut target.normal_delay_ms).clamp_range(DELAY_RANGE_MS)).changed();
+                            }
+                            "Humanized" => {
+                                ui.label("CPS:");
+                                changed |= ui.add(egui::DragValue::new(&mut target.cps).clamp_range(CPS_RANGE)).changed();
+                            }
+                            _ => {}
+                        }
+                    });
                 }
             });
+        }
+        
+        if let Some(index) = remove {
+            targets.remove(index);
+            close_picker = true;
+            changed = true;
+        }
+        
+        if close_picker {
+            self.control_picker = None;
+        }
+        
+        if changed {
+            *self.targets.lock().unwrap() = targets;
+            *self.target_strategy.lock().unwrap() = strategy;
+            *self.target_loss_action.lock().unwrap() = loss_action;
+            *self.click_condition.lock().unwrap() = condition;
+            self.save_current_config();
+        }
+    }
+    
+    fn auto_rules_ui(&mut self, ui: &mut egui::Ui) {
+        let profile_names: Vec<String> = self.profiles.lock().unwrap().iter().map(|p| p.name.clone()).collect();
+        let mut changed = false;
+        
+        egui::CollapsingHeader::new("Auto Profile Rules").show(ui, |ui| {
+            let mut rules = self.auto_rules.lock().unwrap();
+            let mut remove = None;
             
-            let current_mode = self.click_mode.lock().unwrap().clone();
-            if current_mode == ClickMode::Click {
-                ui.horizontal(|ui| {
-                    ui.label("Delay (ms):");
-                    let mut delay = self.normal_delay.lock().unwrap().as_millis() as f32;
-                    if ui.add(egui::Slider::new(&mut delay, 1.0..=1000.0)).changed() {
-                        *self.normal_delay.lock().unwrap() = Duration::from_millis(delay as u64);
-                        self.save_current_config();
-                    }
+            for (index, rule) in rules.iter_mut().enumerate() {
+                ui.push_id(index, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Title regex:");
+                        changed |= ui.add(egui::TextEdit::singleline(&mut rule.title_regex).desired_width(120.0)).changed();
+                        if !rule.title_regex.is_empty() && Regex::new(&rule.title_regex).is_err() {
+                            ui.colored_label(egui::Color32::RED, "Invalid regex");
+                        }
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Executable:");
+                        changed |= ui.add(egui::TextEdit::singleline(&mut rule.executable).desired_width(120.0)).changed();
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Profile:");
+                        egui::ComboBox::from_id_source("rule_profile")
+                            .selected_text(&rule.profile)
+                            .show_ui(ui, |ui| {
+                                for name in &profile_names {
+                                    changed |= ui.selectable_value(&mut rule.profile, name.clone(), name).changed();
+                                }
+                            });
+                        
+                        egui::ComboBox::from_id_source("rule_action")
+                            .selected_text(match rule.action.as_str() {
+                                "Arm" => "Arm clicker",
+                                "Disarm" => "Disarm clicker",
+                                _ => "Profile only",
+                            })
+                            .show_ui(ui, |ui| {
+                                changed |= ui.selectable_value(&mut rule.action, String::new(), "Profile only").changed();
+                                changed |= ui.selectable_value(&mut rule.action, "Arm".to_string(), "Arm clicker").changed();
+                                changed |= ui.selectable_value(&mut rule.action, "Disarm".to_string(), "Disarm clicker").changed();
+                            });
+                        
+                        if ui.button("Remove").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                    
+                    ui.separator();
                 });
             }
             
-            if current_mode == ClickMode::Humanized {
-                ui.horizontal(|ui| {
-                    ui.label("CPS:");
-                    let mut cps = *self.cps.lock().unwrap();
-                    if ui.add(egui::Slider::new(&mut cps, 1.0..=100.0)).changed() {
-                        *self.cps.lock().unwrap() = cps;
-                        self.save_current_config();
-                    }
+            if let Some(index) = remove {
+                rules.remove(index);
+                changed = true;
+            }
+            
+            if ui.button("Add Rule").clicked() {
+                rules.push(AutoProfileRule {
+                    profile: self.active_profile.lock().unwrap().clone(),
+                    ..Default::default()
                 });
-                
-                if *self.cps.lock().unwrap() > 50.0 {
-                    ui.label("Burst mode enabled for very high CPS");
-                }
+                changed = true;
             }
+        });
+        
+        if changed {
+            self.last_foreground = None;
+            self.save_current_config();
+        }
+    }
+}
 
-            ui.separator();
-
-            ui.horizontal(|ui| {
-                if ui.button("Refresh Windows").clicked() {
-                    self.refresh_windows();
-                }
-                
-                if ui.button("Clear Target").clicked() {
-                    *self.target_window.lock().unwrap() = None;
+/// Lists the controls of a target window, indented by depth, and returns the
+/// one the user picks.
+fn control_picker_ui(ui: &mut egui::Ui, controls: &[ChildInfo], close: &mut bool) -> Option<ChildControl> {
+    let mut picked = None;
+    
+    egui::Frame::none()
+        .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY))
+        .inner_margin(egui::Margin::same(8.0))
+        .show(ui, |ui| {
+            if controls.is_empty() {
+                ui.label("The target window has no controls, or it isn't open.");
+            }
+            
+            egui::ScrollArea::vertical().id_source("control_picker").max_height(200.0).show(ui, |ui| {
+                for (position, control) in controls.iter().enumerate() {
+                    let rect = &control.rect;
+                    let label = format!(
+                        "{}{} \"{}\"  ({}, {}) {}x{}",
+                        "    ".repeat(control.depth),
+                        control.class_name,
+                        control.text,
+                        rect.left,
+                        rect.top,
+                        rect.right - rect.left,
+                        rect.bottom - rect.top,
+                    );
+                    
+                    if ui.selectable_label(false, label).clicked() {
+                        picked = Some(ChildControl {
+                            class_name: control.class_name.clone(),
+                            index: controls[..position].iter().filter(|c| c.class_name == control.class_name).count(),
+                            text: control.text.clone(),
+                        });
+                    }
                 }
             });
             
-            if !self.windows.is_empty() {
-                ui.label("Available Windows:");
-                
-                egui::Frame::none()
-                    .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY))
-                    .inner_margin(egui::Margin::same(8.0))
-                    .outer_margin(egui::Margin::same(4.0))
-                    .show(ui, |ui| {
-                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
-                            for window in &self.windows {
-                                let is_selected = {
-                                    let target = self.target_window.lock().unwrap();
-                                    target.as_ref() == Some(window)
-                                };
-                                if ui.selectable_label(is_selected, window).clicked() {
-                                    *self.target_window.lock().unwrap() = Some(window.clone());
-                        }
-                    }
-                });
-                    });
+            if ui.button("Close").clicked() {
+                *close = true;
+            }
+        });
+    
+    picked
+}
+
+/// Collects every visible top-level window, titled or not, since class and
+/// executable rules can match windows without a title.
+unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    if IsWindowVisible(hwnd).into() {
+        let title = window_text(hwnd);
+        if title != "Program Manager" {
+            let window_data = &mut *(lparam.0 as *mut Vec<(HWND, String)>);
+            window_data.push((hwnd, title));
+        }
+    }
+    BOOL::from(true)
+}
+
+/// Parses a profile bundle, upgrading profiles from bundles written before
+/// window match rules (version 1) or multiple targets (version 2) existed.
+fn parse_bundle(bundle_json: &str) -> Result<ProfileBundle, serde_json::Error> {
+    let mut value = serde_json::from_str::<serde_json::Value>(bundle_json)?;
+    let bundle_version = value.get("bundle_version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if let Some(profiles) = value.get_mut("profiles").and_then(|p| p.as_array_mut()) {
+        for profile in profiles {
+            if bundle_version < 2 {
+                migrate_target_window(profile);
+            }
+            if bundle_version < 3 {
+                migrate_target_list(profile);
+            }
+        }
+    }
+    serde_json::from_value(value)
+}
+
+unsafe extern "system" fn enum_child_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let children = &mut *(lparam.0 as *mut Vec<HWND>);
+    children.push(hwnd);
+    BOOL::from(true)
+}
+
+fn auto_rule_matches(rule: &AutoProfileRule, title: &str, executable: &str) -> bool {
+    if rule.title_regex.is_empty() && rule.executable.is_empty() {
+        return false;
+    }
+    
+    let title_matches = rule.title_regex.is_empty()
+        || Regex::new(&rule.title_regex).map(|re| re.is_match(title)).unwrap_or(false);
+    let executable_matches = rule.executable.is_empty()
+        || rule.executable.eq_ignore_ascii_case(executable);
+    
+    title_matches && executable_matches
+}
+
+/// Returns the foreground window with its title and executable name, or `None`
+/// if there is no foreground window or it belongs to this process.
+fn foreground_window_info() -> Option<(HWND, String, String)> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+        
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid == std::process::id() {
+            return None;
+        }
+        
+        Some((hwnd, window_text(hwnd), process_exe_name(pid).unwrap_or_default()))
+    }
+}
+
+fn process_exe_name(pid: u32) -> Option<String> {
+    process_list().into_iter()
+        .find(|(process_id, _)| *process_id == pid)
+        .map(|(_, exe_name)| exe_name)
+}
+
+/// Every running process as `(pid, executable name)`, from one Toolhelp
+/// snapshot.
+fn process_list() -> Vec<(u32, String)> {
+    let mut processes = Vec::new();
+    
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return processes;
+        };
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        
+        let mut more = Process32FirstW(snapshot, &mut entry).as_bool();
+        while more {
+            let length = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+            processes.push((entry.th32ProcessID, String::from_utf16_lossy(&entry.szExeFile[..length])));
+            more = Process32NextW(snapshot, &mut entry).as_bool();
+        }
+        
+        CloseHandle(snapshot);
+    }
+    
+    processes
+}
+
+/// Lists the visible top-level windows. Executable names need a process
+/// snapshot, so they are left empty unless `exe_names` asks for them.
+fn top_level_windows(exe_names: bool) -> Vec<WindowInfo> {
+    let mut window_data = Vec::new();
+    
+    unsafe {
+        EnumWindows(
+            Some(enum_windows_proc),
+            LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
+        );
+    }
+    
+    let processes = if exe_names { process_list() } else { Vec::new() };
+    window_data.into_iter()
+        .map(|(hwnd, title)| {
+            let mut pid = 0u32;
+            unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+            let exe_name = processes.iter()
+                .find(|(process_id, _)| *process_id == pid)
+                .map(|(_, exe_name)| exe_name.clone())
+                .unwrap_or_default();
+            
+            WindowInfo { hwnd, title, class_name: window_class_name(hwnd), pid, exe_name }
+        })
+        .collect()
+}
+
+/// The window's small icon as RGBA pixels and its size: the icon the window
+/// sets for itself, or else its class icon.
+fn window_icon(hwnd: HWND) -> Option<([usize; 2], Vec<u8>)> {
+    let mut icon = 0usize;
+    unsafe {
+        // A hung window must not stall the GUI, so give up on it quickly
+        for kind in [ICON_SMALL2, ICON_SMALL, ICON_BIG] {
+            SendMessageTimeoutW(hwnd, WM_GETICON, WPARAM(kind as usize), LPARAM(0), SMTO_ABORTIFHUNG, 50, Some(&mut icon));
+            if icon != 0 {
+                break;
+            }
+        }
+        if icon == 0 {
+            icon = GetClassLongPtrW(hwnd, GCLP_HICONSM);
+        }
+        if icon == 0 {
+            icon = GetClassLongPtrW(hwnd, GCLP_HICON);
+        }
+    }
+    
+    if icon == 0 {
+        return None;
+    }
+    icon_rgba(HICON(icon as isize))
+}
+
+/// Converts a colour icon to RGBA. Icons without an alpha channel take their
+/// transparency from the mask. Monochrome icons are skipped.
+fn icon_rgba(icon: HICON) -> Option<([usize; 2], Vec<u8>)> {
+    let mut info = ICONINFO::default();
+    unsafe {
+        if !GetIconInfo(icon, &mut info).as_bool() {
+            return None;
+        }
+    }
+    
+    let color = bitmap_bgra(info.hbmColor);
+    let mask = bitmap_bgra(info.hbmMask);
+    unsafe {
+        DeleteObject(info.hbmColor);
+        DeleteObject(info.hbmMask);
+    }
+    
+    let (size, mut pixels) = color?;
+    let has_alpha = pixels.chunks_exact(4).any(|pixel| pixel[3] != 0);
+    let mask = mask.filter(|(mask_size, _)| *mask_size == size);
+    for (index, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        pixel.swap(0, 2);
+        if !has_alpha {
+            let transparent = mask.as_ref().is_some_and(|(_, mask)| mask[index * 4] != 0);
+            pixel[3] = if transparent { 0 } else { 255 };
+        }
+    }
+    Some((size, pixels))
+}
+
+/// Reads a bitmap as top-down rows of 32-bit BGRA pixels.
+fn bitmap_bgra(bitmap: HBITMAP) -> Option<([usize; 2], Vec<u8>)> {
+    if bitmap.0 == 0 {
+        return None;
+    }
+    
+    unsafe {
+        let mut header = BITMAP::default();
+        let size = std::mem::size_of::<BITMAP>() as i32;
+        if GetObjectW(bitmap, size, Some(&mut header as *mut BITMAP as *mut std::ffi::c_void)) == 0 {
+            return None;
+        }
+        let (width, height) = (header.bmWidth, header.bmHeight);
+        
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0 as u32,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let dc = GetDC(HWND(0));
+        let lines = GetDIBits(
+            dc,
+            bitmap,
+            0,
+            height as u32,
+            Some(pixels.as_mut_ptr() as *mut std::ffi::c_void),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+        ReleaseDC(HWND(0), dc);
+        
+        (lines == height).then_some(([width as usize, height as usize], pixels))
+    }
+}
+
+/// Whether a window belongs in the window list: titled, not a tool window,
+/// not cloaked (windows on other virtual desktops or suspended UWP apps), and
+/// not one of ours.
+fn is_listable_window(window: &WindowInfo) -> bool {
+    if window.title.is_empty() || window.pid == std::process::id() {
+        return false;
+    }
+    
+    unsafe {
+        let ex_style = GetWindowLongW(window.hwnd, GWL_EXSTYLE) as u32;
+        if ex_style & WS_EX_TOOLWINDOW.0 != 0 {
+            return false;
+        }
+        
+        let mut cloaked = 0u32;
+        let result = DwmGetWindowAttribute(
+            window.hwnd,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut u32 as *mut std::ffi::c_void,
+            std::mem::size_of::<u32>() as u32,
+        );
+        result.is_err() || cloaked == 0
+    }
+}
+
+fn window_class_name(hwnd: HWND) -> String {
+    let mut buffer = [0u16; 256];
+    let length = unsafe { GetClassNameW(hwnd, &mut buffer) }.max(0) as usize;
+    String::from_utf16_lossy(&buffer[..length])
+}
+
+fn window_text(hwnd: HWND) -> String {
+    unsafe {
+        let length = GetWindowTextLengthW(hwnd);
+        let mut buffer = vec![0u16; (length + 1) as usize];
+        let copied = GetWindowTextW(hwnd, &mut buffer);
+        String::from_utf16_lossy(&buffer[..copied.max(0) as usize])
+    }
+}
+
+/// The top-level window under the cursor and the cursor's position in its
+/// client area, unless that window is one of ours.
+fn window_at_cursor() -> Option<(HWND, POINT)> {
+    unsafe {
+        let mut cursor = POINT::default();
+        if !GetCursorPos(&mut cursor).as_bool() {
+            return None;
+        }
+        
+        let hwnd = GetAncestor(WindowFromPoint(cursor), GA_ROOT);
+        if hwnd.0 == 0 {
+            return None;
+        }
+        
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid == std::process::id() {
+            return None;
+        }
+        
+        ScreenToClient(hwnd, &mut cursor);
+        Some((hwnd, cursor))
+    }
+}
+
+/// Every descendant of `parent`, in the order `EnumChildWindows` visits them.
+fn child_windows(parent: HWND) -> Vec<ChildInfo> {
+    let mut children: Vec<HWND> = Vec::new();
+    
+    unsafe {
+        EnumChildWindows(
+            parent,
+            Some(enum_child_windows_proc),
+            LPARAM(&mut children as *mut Vec<HWND> as isize),
+        );
+    }
+    
+    children.into_iter()
+        .map(|hwnd| unsafe {
+            let mut rect = RECT::default();
+            GetWindowRect(hwnd, &mut rect);
+            let mut corners = [POINT { x: rect.left, y: rect.top }, POINT { x: rect.right, y: rect.bottom }];
+            MapWindowPoints(HWND(0), parent, &mut corners);
+            
+            let mut depth = 0;
+            let mut ancestor = GetParent(hwnd);
+            while ancestor.0 != 0 && ancestor != parent {
+                depth += 1;
+                ancestor = GetParent(ancestor);
             }
             
-            {
-                let target = self.target_window.lock().unwrap();
-                if let Some(ref target_name) = *target {
-                    ui.label(format!("Target: {}", target_name));
-                }
+            ChildInfo {
+                hwnd,
+                class_name: window_class_name(hwnd),
+                text: window_text(hwnd),
+                rect: RECT { left: corners[0].x, top: corners[0].y, right: corners[1].x, bottom: corners[1].y },
+                depth,
             }
-        });
-        
-        ctx.request_repaint_after(Duration::from_millis(16));
+        })
+        .collect()
+}
+
+fn find_child_control(parent: HWND, child: &ChildControl) -> Option<HWND> {
+    child_windows(parent).into_iter()
+        .filter(|c| c.class_name == child.class_name)
+        .nth(child.index)
+        .map(|c| c.hwnd)
+}
+
+/// Returns the first top-level window matched by `rule`. A rule with an empty
+/// pattern or an invalid regex matches nothing.
+fn find_target_window(rule: &WindowMatchRule) -> Option<HWND> {
+    if rule.pattern.is_empty() {
+        return None;
     }
+    
+    let windows = top_level_windows(rule.kind == "Executable");
+    let found = match rule.kind.as_str() {
+        "TitleContains" => windows.iter().find(|w| w.title.contains(&rule.pattern)),
+        "TitleRegex" => {
+            let re = Regex::new(&rule.pattern).ok()?;
+            windows.iter().find(|w| !w.title.is_empty() && re.is_match(&w.title))
+        }
+        "Class" => windows.iter().find(|w| w.class_name == rule.pattern),
+        "Executable" => windows.iter().find(|w| w.exe_name.eq_ignore_ascii_case(&rule.pattern)),
+        _ => windows.iter().find(|w| w.title == rule.pattern),
+    };
+    
+    found.map(|w| w.hwnd)
 }
 
-impl PyladeClickerApp {
-    fn refresh_windows(&mut self) {
-        self.windows.clear();
-        let mut window_data = Vec::new();
+impl TargetCache {
+    /// Returns the window, or the control inside it, that `target` clicks,
+    /// and the target's point translated into that window's client area.
+    /// Windows are only enumerated when the cached handle is gone. While
+    /// nothing matches, enumeration is retried at most once per
+    /// `TARGET_RETRY_INTERVAL`.
+    fn resolve(&mut self, target: &ClickTarget) -> Option<(HWND, Option<POINT>)> {
+        if self.target.as_ref() != Some(target) {
+            *self = TargetCache { target: Some(target.clone()), ..Default::default() };
+        }
+        
+        if let Some(hwnd) = self.hwnd {
+            if unsafe { IsWindow(hwnd) }.as_bool() {
+                return Some((hwnd, self.point));
+            }
+            self.hwnd = None;
+        }
         
-        unsafe {
-            EnumWindows(
-                Some(enum_windows_proc),
-                LPARAM(&mut window_data as *mut Vec<(HWND, String)> as isize),
-            );
+        if self.last_miss.is_some_and(|missed| missed.elapsed() < TARGET_RETRY_INTERVAL) {
+            return None;
         }
         
-        self.windows = window_data.into_iter().map(|(_, title)| title).collect();
+        let window = find_target_window(&target.rule);
+        self.hwnd = window.and_then(|hwnd| match &target.child {
+            Some(child) => find_child_control(hwnd, child),
+            None => Some(hwnd),
+        });
+        self.last_miss = if self.hwnd.is_none() { Some(Instant::now()) } else { None };
+        
+        let (window, hwnd) = (window?, self.hwnd?);
+        self.point = target.point.map(|(x, y)| {
+            let mut points = [POINT { x, y }];
+            if hwnd != window {
+                unsafe { MapWindowPoints(window, hwnd, &mut points) };
+            }
+            points[0]
+        });
+        Some((hwnd, self.point))
     }
 }
 
-unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
-    if IsWindowVisible(hwnd).into() {
-        let length = GetWindowTextLengthW(hwnd);
-        if length > 0 {
-            let mut buffer = vec![0u16; (length + 1) as usize];
-            GetWindowTextW(hwnd, &mut buffer);
-            let title = String::from_utf16_lossy(&buffer[..length as usize]);
-            if !title.is_empty() && title != "Program Manager" {
-                let window_data = &mut *(lparam.0 as *mut Vec<(HWND, String)>);
-                window_data.push((hwnd, title));
+fn window_match_kind_label(kind: &str) -> &'static str {
+    match kind {
+        "TitleContains" => "Title contains",
+        "TitleRegex" => "Title regex",
+        "Class" => "Window class",
+        "Executable" => "Executable",
+        _ => "Exact title",
+    }
+}
+
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+fn get_config_path() -> PathBuf {
+    CONFIG_PATH.get_or_init(resolve_config_path).clone()
+}
+
+/// Picks the config file in order of precedence: a `--config <path>`
+/// argument, a `config.json` next to the executable (portable mode), then the
+/// per-user config directory. A config left in the old Documents location is
+/// copied to the per-user directory the first time it is needed.
+fn resolve_config_path() -> PathBuf {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(path) = args.next() {
+                return PathBuf::from(path);
             }
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return PathBuf::from(path);
         }
     }
-    BOOL::from(true)
+    
+    if let Some(portable_path) = portable_config_path() {
+        if portable_path.is_file() {
+            return portable_path;
+        }
+    }
+    
+    let config_path = match dirs::config_dir() {
+        Some(dir) => dir.join("PyladeClicker").join("config.json"),
+        None => PathBuf::from("config.json"),
+    };
+    
+    if !config_path.exists() {
+        if let Some(legacy_path) = legacy_config_path() {
+            if legacy_path.is_file() {
+                if let Some(parent) = config_path.parent() {
+                    let _ = fs::create_dir_all(parent);
+                }
+                let _ = fs::copy(&legacy_path, &config_path);
+            }
+        }
+    }
+    
+    config_path
 }
 
-fn get_config_path() -> PathBuf {
-    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("C:\\"));
-    path.push("Documents");
-    path.push("PyladeClicker");
-    path.push("config.json");
-    path
+fn portable_config_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join("config.json"))
+}
+
+fn legacy_config_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join("Documents").join("PyladeClicker").join("config.json"))
 }
 
-fn load_config() -> AppConfig {
+/// Reads the config file, migrating older layouts and validating every field.
+/// Returns a description of anything that had to be discarded or corrected,
+/// in which case the original file has been backed up next to it.
+/// Loads and validates the config. Alongside the report of any problems, says
+/// whether the corrected config should be saved, which is only once the
+/// original has been backed up.
+fn load_config() -> (AppConfig, Option<String>, bool) {
     let config_path = get_config_path();
     
-    if let Ok(config_data) = fs::read_to_string(&config_path) {
-        if let Ok(config) = serde_json::from_str::<AppConfig>(&config_data) {
-            return config;
+    let Ok(config_data) = fs::read_to_string(&config_path) else {
+        return (AppConfig::default(), None, false);
+    };
+    
+    let (config, errors) = match parse_config(&config_data) {
+        Ok(mut config) => {
+            let errors = validate_config(&mut config);
+            (config, errors)
+        }
+        Err(error) => (AppConfig::default(), vec![error]),
+    };
+    
+    if errors.is_empty() {
+        return (config, None, false);
+    }
+    
+    let (report, backed_up) = report_config_errors(&errors, &config_path);
+    (config, Some(report), backed_up)
+}
+
+/// Backs up the config file before its problems get corrected and describes
+/// both for the GUI.
+fn report_config_errors(errors: &[String], config_path: &Path) -> (String, bool) {
+    let backup_path = backup_config_file(config_path);
+    let backup_note = match &backup_path {
+        Some(backup_path) => format!("The original file was backed up to {}.", backup_path.display()),
+        None => "The original file could not be backed up.".to_string(),
+    };
+    
+    (format!("{}\n{}", errors.join("\n"), backup_note), backup_path.is_some())
+}
+
+fn parse_config(config_data: &str) -> Result<AppConfig, String> {
+    let value = serde_json::from_str::<serde_json::Value>(config_data)
+        .map_err(|e| format!("Config is not valid JSON: {}", e))?;
+    let value = migrate_config(value)?;
+    
+    serde_json::from_value::<AppConfig>(value)
+        .map_err(|e| format!("Config has an invalid field: {}", e))
+}
+
+/// Upgrades a config value to `CONFIG_VERSION` one step at a time. Files
+/// written before the `version` field existed are version 1 if they hold a
+/// single flat profile and version 2 if they already have `profiles`.
+fn migrate_config(mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut version = match value.get("version") {
+        Some(version) => version.as_u64().ok_or("Config version is not a number")? as u32,
+        None if value.get("profiles").is_some() => 2,
+        None => 1,
+    };
+    
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "Config version {} is newer than this build supports (version {})",
+            version, CONFIG_VERSION
+        ));
+    }
+    
+    while version < CONFIG_VERSION {
+        value = match version {
+            1 => migrate_v1_to_v2(value)?,
+            2 => migrate_v2_to_v3(value),
+            3 => migrate_v3_to_v4(value),
+            _ => return Err(format!("No migration from config version {}", version)),
+        };
+        version += 1;
+    }
+    
+    value["version"] = serde_json::Value::from(CONFIG_VERSION);
+    Ok(value)
+}
+
+/// Version 1 held one profile's settings at the top level. They become the
+/// single "Default" profile.
+fn migrate_v1_to_v2(value: serde_json::Value) -> Result<serde_json::Value, String> {
+    let serde_json::Value::Object(mut profile) = value else {
+        return Err("Config is not a JSON object".to_string());
+    };
+    
+    let mut migrated = serde_json::Map::new();
+    for key in ["start_hotkey", "stop_hotkey", "panic_hotkey"] {
+        if let Some(hotkey) = profile.remove(key) {
+            migrated.insert(key.to_string(), hotkey);
+        }
+    }
+    profile.insert("name".to_string(), serde_json::Value::from("Default"));
+    migrated.insert("active_profile".to_string(), serde_json::Value::from("Default"));
+    migrated.insert("profiles".to_string(), serde_json::Value::Array(vec![serde_json::Value::Object(profile)]));
+    
+    Ok(serde_json::Value::Object(migrated))
+}
+
+/// Version 2 targeted windows by exact title only, stored as a plain string.
+fn migrate_v2_to_v3(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(profiles) = value.get_mut("profiles").and_then(|p| p.as_array_mut()) {
+        profiles.iter_mut().for_each(migrate_target_window);
+    }
+    value
+}
+
+/// Version 3 had at most one target per profile, stored as `target_window`.
+fn migrate_v3_to_v4(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(profiles) = value.get_mut("profiles").and_then(|p| p.as_array_mut()) {
+        profiles.iter_mut().for_each(migrate_target_list);
+    }
+    value
+}
+
+/// Moves a profile's single `target_window` rule into its `targets` list,
+/// scheduled like the profile itself.
+fn migrate_target_list(profile: &mut serde_json::Value) {
+    let Some(profile) = profile.as_object_mut() else {
+        return;
+    };
+    let Some(rule) = profile.remove("target_window") else {
+        return;
+    };
+    if rule.is_null() {
+        return;
+    }
+    
+    let mut target = serde_json::Map::new();
+    target.insert("rule".to_string(), rule);
+    for key in ["click_mode", "normal_delay_ms", "cps"] {
+        if let Some(value) = profile.get(key) {
+            target.insert(key.to_string(), value.clone());
+        }
+    }
+    profile.insert("targets".to_string(), serde_json::Value::Array(vec![serde_json::Value::Object(target)]));
+}
+
+/// Turns a profile's exact-title `target_window` string into a window match
+/// rule. Also used for bundles exported before rules existed.
+fn migrate_target_window(profile: &mut serde_json::Value) {
+    let Some(target) = profile.get_mut("target_window") else {
+        return;
+    };
+    if let Some(title) = target.as_str() {
+        *target = serde_json::json!({ "kind": "ExactTitle", "pattern": title });
+    }
+}
+
+/// Checks every field against what the GUI can produce, correcting anything
+/// out of range or unknown. Returns one message per corrected field.
+fn validate_config(config: &mut AppConfig) -> Vec<String> {
+    let mut errors = Vec::new();
+    
+    if config.profiles.is_empty() {
+        errors.push("No profiles were defined; added a default profile".to_string());
+        config.profiles.push(ProfileConfig::default());
+    }
+    
+    let mut seen_names: Vec<String> = Vec::new();
+    for (index, profile) in config.profiles.iter_mut().enumerate() {
+        if profile.name.trim().is_empty() {
+            errors.push(format!("Profile {} has no name", index + 1));
+            profile.name = format!("Profile {}", index + 1);
+        }
+        if seen_names.contains(&profile.name) {
+            let mut n = 2;
+            while seen_names.contains(&format!("{} {}", profile.name, n)) {
+                n += 1;
+            }
+            let renamed = format!("{} {}", profile.name, n);
+            errors.push(format!("Profile name \"{}\" is used twice; renamed to \"{}\"", profile.name, renamed));
+            profile.name = renamed;
+        }
+        seen_names.push(profile.name.clone());
+        
+        let name = profile.name.clone();
+        
+        if !profile.cps.is_finite() || !CPS_RANGE.contains(&profile.cps) {
+            errors.push(format!(
+                "Profile \"{}\": cps {} is outside {}-{}",
+                name, profile.cps, CPS_RANGE.start(), CPS_RANGE.end()
+            ));
+            profile.cps = if profile.cps.is_finite() {
+                profile.cps.clamp(*CPS_RANGE.start(), *CPS_RANGE.end())
+            } else {
+                ProfileConfig::default().cps
+            };
+        }
+        
+        if !DELAY_RANGE_MS.contains(&profile.normal_delay_ms) {
+            errors.push(format!(
+                "Profile \"{}\": normal_delay_ms {} is outside {}-{}",
+                name, profile.normal_delay_ms, DELAY_RANGE_MS.start(), DELAY_RANGE_MS.end()
+            ));
+            profile.normal_delay_ms = profile.normal_delay_ms.clamp(*DELAY_RANGE_MS.start(), *DELAY_RANGE_MS.end());
+        }
+        
+        if !CLICK_MODES.contains(&profile.click_mode.as_str()) {
+            errors.push(format!("Profile \"{}\": unknown click_mode \"{}\"", name, profile.click_mode));
+            profile.click_mode = ProfileConfig::default().click_mode;
+        }
+        
+        if !CLICK_TYPES.contains(&profile.click_type.as_str()) {
+            errors.push(format!("Profile \"{}\": unknown click_type \"{}\"", name, profile.click_type));
+            profile.click_type = ProfileConfig::default().click_type;
+        }
+        
+        if !ACTIVATION_STYLES.contains(&profile.activation_style.as_str()) {
+            errors.push(format!(
+                "Profile \"{}\": unknown activation_style \"{}\"",
+                name, profile.activation_style
+            ));
+            profile.activation_style = ProfileConfig::default().activation_style;
         }
+        
+        if profile.one_shot_clicks == 0 {
+            errors.push(format!("Profile \"{}\": one_shot_clicks must be at least 1", name));
+            profile.one_shot_clicks = 1;
+        }
+        
+        if !TARGET_STRATEGIES.contains(&profile.target_strategy.as_str()) {
+            errors.push(format!(
+                "Profile \"{}\": unknown target_strategy \"{}\"",
+                name, profile.target_strategy
+            ));
+            profile.target_strategy = ProfileConfig::default().target_strategy;
+        }
+        
+        if !TARGET_LOSS_ACTIONS.contains(&profile.target_loss_action.as_str()) {
+            errors.push(format!(
+                "Profile \"{}\": unknown target_loss_action \"{}\"",
+                name, profile.target_loss_action
+            ));
+            profile.target_loss_action = ProfileConfig::default().target_loss_action;
+        }
+        
+        if !PAUSE_IDLE_RANGE_MS.contains(&profile.pause_idle_ms) {
+            errors.push(format!(
+                "Profile \"{}\": pause_idle_ms {} is outside {}-{}",
+                name, profile.pause_idle_ms, PAUSE_IDLE_RANGE_MS.start(), PAUSE_IDLE_RANGE_MS.end()
+            ));
+            profile.pause_idle_ms = profile.pause_idle_ms.clamp(*PAUSE_IDLE_RANGE_MS.start(), *PAUSE_IDLE_RANGE_MS.end());
+        }
+        
+        if !CLICK_CONDITIONS.contains(&profile.click_condition.as_str()) {
+            errors.push(format!(
+                "Profile \"{}\": unknown click_condition \"{}\"",
+                name, profile.click_condition
+            ));
+            profile.click_condition = ProfileConfig::default().click_condition;
+        }
+        
+        for (target_index, target) in profile.targets.iter_mut().enumerate() {
+            let field = format!("Profile \"{}\": target {}", name, target_index + 1);
+            if !WINDOW_MATCH_KINDS.contains(&target.rule.kind.as_str()) {
+                errors.push(format!("{}: unknown kind \"{}\"", field, target.rule.kind));
+                target.rule.kind = WINDOW_MATCH_KINDS[0].to_string();
+            }
+            if target.rule.kind == "TitleRegex" && Regex::new(&target.rule.pattern).is_err() {
+                errors.push(format!("{}: regex \"{}\" is not valid; matching it as plain text", field, target.rule.pattern));
+                target.rule.pattern = regex::escape(&target.rule.pattern);
+            }
+            if !CLICK_MODES.contains(&target.click_mode.as_str()) {
+                errors.push(format!("{}: unknown click_mode \"{}\"", field, target.click_mode));
+                target.click_mode = ClickTarget::default().click_mode;
+            }
+            if !DELAY_RANGE_MS.contains(&target.normal_delay_ms) {
+                errors.push(format!("{}: normal_delay_ms {} is out of range", field, target.normal_delay_ms));
+                target.normal_delay_ms = target.normal_delay_ms.clamp(*DELAY_RANGE_MS.start(), *DELAY_RANGE_MS.end());
+            }
+            if !target.cps.is_finite() || !CPS_RANGE.contains(&target.cps) {
+                errors.push(format!("{}: cps {} is out of range", field, target.cps));
+                target.cps = ClickTarget::default().cps;
+            }
+        }
+        
+        validate_hotkey(&mut profile.hotkey, &format!("Profile \"{}\": hotkey", name), &mut errors);
+        validate_hotkey(&mut profile.switch_hotkey, &format!("Profile \"{}\": switch_hotkey", name), &mut errors);
+    }
+    
+    if !config.profiles.iter().any(|p| p.name == config.active_profile) {
+        errors.push(format!("Active profile \"{}\" does not exist", config.active_profile));
+        config.active_profile = config.profiles[0].name.clone();
+    }
+    
+    validate_hotkey(&mut config.start_hotkey, "start_hotkey", &mut errors);
+    validate_hotkey(&mut config.stop_hotkey, "stop_hotkey", &mut errors);
+    validate_hotkey(&mut config.panic_hotkey, "panic_hotkey", &mut errors);
+    validate_hotkey(&mut config.pick_target_hotkey, "pick_target_hotkey", &mut errors);
+    
+    if !FAILSAFE_ZONES.contains(&config.failsafe.as_str()) {
+        errors.push(format!("unknown failsafe \"{}\"", config.failsafe));
+        config.failsafe = AppConfig::default().failsafe;
+    }
+    
+    let [left, top, right, bottom] = config.failsafe_rect;
+    if left >= right || top >= bottom {
+        errors.push(format!("failsafe_rect {:?} is empty", config.failsafe_rect));
+        config.failsafe_rect = AppConfig::default().failsafe_rect;
     }
     
-    AppConfig::default()
+    let mut index = 0;
+    config.auto_rules.retain_mut(|rule| {
+        index += 1;
+        if !config.profiles.iter().any(|p| p.name == rule.profile) {
+            errors.push(format!("Auto rule {}: profile \"{}\" does not exist; removed the rule", index, rule.profile));
+            return false;
+        }
+        if !rule.title_regex.is_empty() && Regex::new(&rule.title_regex).is_err() {
+            errors.push(format!(
+                "Auto rule {}: title_regex \"{}\" is not a valid regex; matching it as plain text",
+                index, rule.title_regex
+            ));
+            rule.title_regex = regex::escape(&rule.title_regex);
+        }
+        if !AUTO_RULE_ACTIONS.contains(&rule.action.as_str()) {
+            errors.push(format!("Auto rule {}: unknown action \"{}\"", index, rule.action));
+            rule.action = String::new();
+        }
+        true
+    });
+    
+    errors
+}
+
+fn validate_hotkey(hotkey: &mut Vec<String>, field: &str, errors: &mut Vec<String>) {
+    let unknown: Vec<String> = hotkey.iter()
+        .filter(|s| string_to_hotkey_input(s).is_none())
+        .cloned()
+        .collect();
+    
+    if !unknown.is_empty() {
+        errors.push(format!("{}: unknown keys {}", field, unknown.join(", ")));
+        hotkey.retain(|s| string_to_hotkey_input(s).is_some());
+    }
+}
+
+fn backup_config_file(config_path: &Path) -> Option<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let backup_path = config_path.with_file_name(format!("config.invalid-{}.json", timestamp));
+    fs::copy(config_path, &backup_path).ok()?;
+    Some(backup_path)
 }
 
-fn save_config(config: &AppConfig) {
+/// Writes the config to a temporary file next to it and renames it into
+/// place, so a crash mid-write never leaves a truncated config behind.
+fn save_config(config: &AppConfig) -> std::io::Result<()> {
     let config_path = get_config_path();
     
     if let Some(parent) = config_path.parent() {
-        let _ = fs::create_dir_all(parent);
+        fs::create_dir_all(parent)?;
     }
     
-    if let Ok(config_json) = serde_json::to_string_pretty(config) {
-        let _ = fs::write(&config_path, config_json);
+    let config_json = serde_json::to_string_pretty(config)
+        .map_err(std::io::Error::other)?;
+    
+    let temp_path = config_path.with_extension("json.tmp");
+    {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(config_json.as_bytes())?;
+        file.sync_all()?;
     }
+    fs::rename(&temp_path, &config_path)
 }
 
+fn config_file_mtime() -> Option<SystemTime> {
+    fs::metadata(get_config_path()).and_then(|m| m.modified()).ok()
+}
 
-fn string_to_key(s: &str) -> Option<Key> {
-    match s {
-        "F1" => Some(Key::F1),
-        "F2" => Some(Key::F2),
-        "F3" => Some(Key::F3),
-        "F4" => Some(Key::F4),
-        "F5" => Some(Key::F5),
-        "F6" => Some(Key::F6),
-        "F7" => Some(Key::F7),
-        "F8" => Some(Key::F8),
-        "F9" => Some(Key::F9),
-        "F10" => Some(Key::F10),
-        "F11" => Some(Key::F11),
-        "F12" => Some(Key::F12),
-        "Space" => Some(Key::Space),
-        "Enter" => Some(Key::Return),
-        "Escape" => Some(Key::Escape),
-        "Tab" => Some(Key::Tab),
-        "Home" => Some(Key::Home),
-        "End" => Some(Key::End),
-        "PageUp" => Some(Key::PageUp),
-        "PageDown" => Some(Key::PageDown),
-        "Insert" => Some(Key::Insert),
-        "Delete" => Some(Key::Delete),
-        "Up" => Some(Key::UpArrow),
-        "Down" => Some(Key::DownArrow),
-        "Left" => Some(Key::LeftArrow),
-        "Right" => Some(Key::RightArrow),
-        "Backspace" => Some(Key::Backspace),
-        _ => None,
-    }
+fn start_config_saver_thread(app: PyladeClickerApp) {
+    std::thread::spawn(move || loop {
+        thread::sleep(Duration::from_millis(100));
+        
+        // Saving now would silently overwrite an external change the user
+        // hasn't decided about yet.
+        if app.external_config.lock().unwrap().is_some() {
+            continue;
+        }
+        
+        let due = {
+            let mut save_requested = app.save_requested.lock().unwrap();
+            match *save_requested {
+                Some(requested) if requested.elapsed() >= CONFIG_SAVE_DEBOUNCE => {
+                    *save_requested = None;
+                    true
+                }
+                _ => false,
+            }
+        };
+        
+        if due {
+            app.flush_config();
+        }
+    });
+}
+
+/// Polls the config file's modification time and reloads it when something
+/// other than `flush_config` has written to it.
+fn start_config_watcher_thread(app: PyladeClickerApp) {
+    std::thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(1));
+        
+        let changed = {
+            let mut config_mtime = app.config_mtime.lock().unwrap();
+            let current = config_file_mtime();
+            if current.is_some() && current != *config_mtime {
+                *config_mtime = current;
+                true
+            } else {
+                false
+            }
+        };
+        
+        if changed {
+            app.reload_config_from_disk();
+        }
+    });
 }
 
+
 fn load_icon_data() -> egui::IconData {
     let icon_data = include_bytes!("../icon.ico");
     
@@ -1170,35 +4134,286 @@ fn load_icon_data() -> egui::IconData {
 }
 
 fn main() {
+    // Release builds abort on panic, so the hook is the last chance to let go
+    // of anything held down
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        release_tracked_inputs();
+        default_hook(info);
+    }));
+    
     let app = PyladeClickerApp::default();
-    let hotkey = Arc::clone(&app.hotkey);
-    let clicking = Arc::clone(&app.clicking);
-    let _capturing_hotkey = Arc::clone(&app.capturing_hotkey);
-    let _listening_text = Arc::clone(&app.listening_text);
-    let click_mode = Arc::clone(&app.click_mode);
-    let click_type = Arc::clone(&app.click_type);
-    let target_window = Arc::clone(&app.target_window);
-    let _last_click_time = Arc::clone(&app._last_click_time);
-    let _humanized_delay = Arc::clone(&app._humanized_delay);
-    let normal_delay = Arc::clone(&app.normal_delay);
-    let cps = Arc::clone(&app.cps);
-    let is_holding = Arc::clone(&app.is_holding);
-    
-    start_hotkey_toggle_listener(hotkey.clone(), clicking.clone());
-    
-    start_clicking_thread(
-        clicking.clone(),
-        click_mode,
-        click_type,
-        target_window,
-        _last_click_time,
-        _humanized_delay,
-        normal_delay,
-        cps,
-        is_holding,
-    );
+    
+    start_hotkey_toggle_listener(app.clone());
+    start_config_saver_thread(app.clone());
+    start_config_watcher_thread(app.clone());
+    start_clicking_thread(app.clone());
 
     let mut native_options = eframe::NativeOptions::default();
     native_options.viewport = native_options.viewport.with_icon(load_icon_data());
     let _ = eframe::run_native("Pylade Clicker", native_options, Box::new(|_cc| Box::new(app)));
-}
\ No newline at end of file
+    release_tracked_inputs();
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CTRL_F6: &[HotkeyInput] = &[HotkeyInput::Key(Key::ControlLeft), HotkeyInput::Key(Key::F6)];
+    
+    fn run(detector: &mut ChordDetector, chord: &[HotkeyInput], events: &[EventType]) -> Vec<ChordEvent> {
+        events.iter().filter_map(|event| detector.handle(chord, event, |_| true)).collect()
+    }
+    
+    #[test]
+    fn key_table_round_trips() {
+        for &(key, name, vk) in KEY_TABLE {
+            assert_eq!(string_to_key(name), Some(key), "{}", name);
+            assert_eq!(key_to_string(&key), name);
+            assert_eq!(key_to_vk(&key), vk, "{}", name);
+            
+            let input = HotkeyInput::Key(key);
+            assert_eq!(string_to_hotkey_input(&hotkey_input_to_string(&input)), Some(input), "{}", name);
+        }
+    }
+    
+    #[test]
+    fn key_table_names_are_unique() {
+        for (index, &(key, name, _)) in KEY_TABLE.iter().enumerate() {
+            for &(other_key, other_name, _) in &KEY_TABLE[index + 1..] {
+                assert_ne!(name, other_name);
+                assert_ne!(key, other_key, "{} and {}", name, other_name);
+            }
+        }
+    }
+    
+    #[test]
+    fn legacy_key_names_still_parse() {
+        assert_eq!(string_to_key("PageUp"), Some(Key::PageUp));
+        assert_eq!(string_to_key("ShiftLeft"), Some(Key::ShiftLeft));
+        assert_eq!(string_to_key("KeyA"), Some(Key::KeyA));
+        assert_eq!(string_to_key("Unknown(42)"), Some(Key::Unknown(42)));
+        assert_eq!(key_to_string(&Key::Unknown(42)), "Unknown(42)");
+        assert_eq!(string_to_key("NotAKey"), None);
+    }
+    
+    #[test]
+    fn mouse_buttons_round_trip() {
+        for (name, button) in [("Middle Mouse", Button::Middle), ("Mouse 4", Button::Unknown(1)), ("Mouse 5", Button::Unknown(2))] {
+            let input = HotkeyInput::Button(button);
+            assert_eq!(string_to_hotkey_input(name), Some(input));
+            assert_eq!(hotkey_input_to_string(&input), name);
+        }
+    }
+    
+    #[test]
+    fn validation_corrects_bad_rules_and_regexes() {
+        let mut config = AppConfig::default();
+        config.profiles[0].targets.push(ClickTarget {
+            rule: WindowMatchRule { kind: "TitleRegex".to_string(), pattern: "Game (64-bit".to_string() },
+            ..Default::default()
+        });
+        config.auto_rules = vec![
+            AutoProfileRule { title_regex: "[unclosed".to_string(), profile: config.active_profile.clone(), ..Default::default() },
+            AutoProfileRule { executable: "game.exe".to_string(), profile: "Deleted".to_string(), ..Default::default() },
+        ];
+        
+        assert_eq!(validate_config(&mut config).len(), 3);
+        assert_eq!(config.auto_rules.len(), 1);
+        assert!(Regex::new(&config.auto_rules[0].title_regex).unwrap().is_match("[unclosed"));
+        assert!(Regex::new(&config.profiles[0].targets[0].rule.pattern).unwrap().is_match("Game (64-bit"));
+        assert!(validate_config(&mut config).is_empty());
+    }
+    
+    /// Benchmark rather than a test: prints what looking up a target costs per
+    /// click with a full window search, as every click did before the target
+    /// cache, and with a cached lookup. See the README for how to run it.
+    #[cfg(windows)]
+    #[test]
+    #[ignore]
+    fn bench_target_lookup() {
+        let window = top_level_windows(false).into_iter().next().expect("no top-level windows");
+        let target = ClickTarget {
+            rule: WindowMatchRule { kind: "Class".to_string(), pattern: window.class_name },
+            ..Default::default()
+        };
+        const ROUNDS: u32 = 1000;
+        
+        let started = Instant::now();
+        for _ in 0..ROUNDS {
+            assert!(find_target_window(&target.rule).is_some());
+        }
+        let uncached = started.elapsed() / ROUNDS;
+        
+        let mut cache = TargetCache::default();
+        let started = Instant::now();
+        for _ in 0..ROUNDS {
+            assert!(cache.resolve(&target).is_some());
+        }
+        let cached = started.elapsed() / ROUNDS;
+        
+        println!("find_target_window: {:?} per call, cached resolve: {:?} per call", uncached, cached);
+    }
+    
+    fn left_up(hwnd: isize, lparam: isize) -> HeldInput {
+        HeldInput::Window { hwnd, message: WM_LBUTTONUP, wparam: 0, lparam }
+    }
+    
+    #[test]
+    fn tracked_press_then_release_leaves_nothing_held() {
+        let mut tracker = InputTracker::new();
+        tracker.press(HeldInput::Global(EventType::ButtonRelease(Button::Left)));
+        tracker.press(left_up(1, 10));
+        tracker.release(HeldInput::Global(EventType::ButtonRelease(Button::Left)));
+        tracker.release(left_up(1, 10));
+        
+        let mut sent = Vec::new();
+        tracker.release_all(|input| sent.push(*input));
+        assert_eq!(sent, vec![]);
+    }
+    
+    #[test]
+    fn release_all_sends_each_release_once_in_reverse_order() {
+        let mut tracker = InputTracker::new();
+        let inputs = [
+            HeldInput::Global(EventType::ButtonRelease(Button::Left)),
+            HeldInput::Global(EventType::KeyRelease(Key::Space)),
+            left_up(1, 10),
+        ];
+        for input in inputs {
+            tracker.press(input);
+        }
+        tracker.press(inputs[0]);
+        
+        let mut sent = Vec::new();
+        tracker.release_all(|input| sent.push(*input));
+        assert_eq!(sent, vec![inputs[2], inputs[1], inputs[0]]);
+        
+        tracker.release_all(|input| sent.push(*input));
+        assert_eq!(sent.len(), 3);
+    }
+    
+    #[test]
+    fn same_button_at_different_points_is_held_once() {
+        let mut tracker = InputTracker::new();
+        tracker.press(left_up(1, 10));
+        tracker.press(left_up(1, 20));
+        tracker.press(left_up(2, 10));
+        tracker.release(left_up(1, 30));
+        
+        let mut sent = Vec::new();
+        tracker.release_all(|input| sent.push(*input));
+        assert_eq!(sent, vec![left_up(2, 10)]);
+    }
+    
+    #[test]
+    fn chord_fires_once_per_press() {
+        let mut detector = ChordDetector::default();
+        let events = [
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyPress(Key::F6),
+            EventType::KeyRelease(Key::F6),
+            EventType::KeyRelease(Key::ControlLeft),
+        ];
+        assert_eq!(run(&mut detector, CTRL_F6, &events), vec![ChordEvent::Pressed, ChordEvent::Released]);
+    }
+    
+    #[test]
+    fn partial_chord_does_not_fire() {
+        let mut detector = ChordDetector::default();
+        let events = [
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyRelease(Key::ControlLeft),
+            EventType::KeyPress(Key::F6),
+            EventType::KeyRelease(Key::F6),
+        ];
+        assert_eq!(run(&mut detector, CTRL_F6, &events), vec![]);
+    }
+    
+    #[test]
+    fn auto_repeat_does_not_fire_again() {
+        let mut detector = ChordDetector::default();
+        let events = [
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyPress(Key::F6),
+            EventType::KeyPress(Key::F6),
+            EventType::KeyPress(Key::F6),
+            EventType::KeyPress(Key::ControlLeft),
+        ];
+        assert_eq!(run(&mut detector, CTRL_F6, &events), vec![ChordEvent::Pressed]);
+    }
+    
+    #[test]
+    fn chord_fires_with_other_keys_held() {
+        let mut detector = ChordDetector::default();
+        let events = [
+            EventType::KeyPress(Key::KeyW),
+            EventType::KeyPress(Key::F6),
+            EventType::KeyRelease(Key::F6),
+        ];
+        let f6 = &[HotkeyInput::Key(Key::F6)];
+        assert_eq!(run(&mut detector, f6, &events), vec![ChordEvent::Pressed, ChordEvent::Released]);
+    }
+    
+    #[test]
+    fn panic_chord_fires_while_movement_keys_are_held() {
+        let mut detector = ChordDetector::default();
+        let panic_chord = &[HotkeyInput::Key(Key::ControlLeft), HotkeyInput::Key(Key::Escape)];
+        let events = [
+            EventType::KeyPress(Key::KeyW),
+            EventType::KeyPress(Key::ShiftLeft),
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyPress(Key::Escape),
+        ];
+        assert_eq!(run(&mut detector, panic_chord, &events), vec![ChordEvent::Pressed]);
+    }
+    
+    #[test]
+    fn missed_release_is_forgotten_on_next_press() {
+        let mut detector = ChordDetector::default();
+        let f6 = &[HotkeyInput::Key(Key::F6)];
+        assert_eq!(detector.handle(f6, &EventType::KeyPress(Key::F6), |_| true), Some(ChordEvent::Pressed));
+        
+        // The release of F6 never arrives; the next press finds it up
+        let up = |_: &HotkeyInput| false;
+        assert_eq!(detector.handle(f6, &EventType::KeyPress(Key::F6), up), Some(ChordEvent::Pressed));
+        assert_eq!(detector.handle(f6, &EventType::KeyRelease(Key::F6), up), Some(ChordEvent::Released));
+    }
+    
+    #[test]
+    fn missed_chord_release_releases_on_other_press() {
+        let mut detector = ChordDetector::default();
+        let f6 = &[HotkeyInput::Key(Key::F6)];
+        assert_eq!(detector.handle(f6, &EventType::KeyPress(Key::F6), |_| true), Some(ChordEvent::Pressed));
+        
+        let up = |input: &HotkeyInput| *input != HotkeyInput::Key(Key::F6);
+        assert_eq!(detector.handle(f6, &EventType::KeyPress(Key::KeyW), up), Some(ChordEvent::Released));
+    }
+    
+    #[test]
+    fn releasing_any_chord_key_releases() {
+        for released in [Key::ControlLeft, Key::F6] {
+            let mut detector = ChordDetector::default();
+            let events = [
+                EventType::KeyPress(Key::ControlLeft),
+                EventType::KeyPress(Key::F6),
+                EventType::KeyRelease(released),
+            ];
+            assert_eq!(run(&mut detector, CTRL_F6, &events), vec![ChordEvent::Pressed, ChordEvent::Released]);
+        }
+    }
+    
+    #[test]
+    fn pressing_again_under_held_modifier_fires_again() {
+        let mut detector = ChordDetector::default();
+        let events = [
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyPress(Key::F6),
+            EventType::KeyRelease(Key::F6),
+            EventType::KeyPress(Key::F6),
+        ];
+        assert_eq!(
+            run(&mut detector, CTRL_F6, &events),
+            vec![ChordEvent::Pressed, ChordEvent::Released, ChordEvent::Pressed]
+        );
+    }
+}